@@ -1,46 +1,381 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
 #[derive(Debug)]
 enum Error {
-    InvalidInput,
+    NoSolution,
+    Overflow,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NoSolution => write!(f, "no solution"),
+            Error::Overflow => write!(f, "overflow"),
+        }
+    }
 }
 
-fn find_n(nums: &[i32], sum: i32, start: usize, n: i32, vs: &mut Vec<i32>) -> bool {
-    for i in start..nums.len() {
-        let ssum = sum + nums[i];
-        if ssum > 2020 {
+fn find_2_sum(nums: &[i64], target: i64) -> Option<Vec<i64>> {
+    let mut seen = HashSet::new();
+    for &n in nums {
+        // If the difference overflows, no i64 can complete the pair
+        if let Some(m) = target.checked_sub(n) {
+            if seen.contains(&m) {
+                return Some(vec![m, n]);
+            }
+        }
+        seen.insert(n);
+    }
+
+    None
+}
+
+fn find_sorted(nums: &[i64], target: i128, k: usize) -> Option<Vec<i64>> {
+    if nums.len() < k {
+        return None;
+    }
+
+    if k == 2 {
+        let (mut lo, mut hi) = (0, nums.len() - 1);
+        while lo < hi {
+            let sum = nums[lo] as i128 + nums[hi] as i128;
+            if sum == target {
+                return Some(vec![nums[lo], nums[hi]]);
+            } else if sum < target {
+                lo += 1;
+            } else {
+                hi -= 1;
+            }
+        }
+        return None;
+    }
+
+    let max = *nums.last().unwrap() as i128;
+    for i in 0..=nums.len() - k {
+        if i > 0 && nums[i] == nums[i - 1] {
+            continue;
+        }
+
+        // nums[i] is the smallest of the k numbers, the others are at most max
+        let n = nums[i] as i128;
+        if n * k as i128 > target {
+            break;
+        }
+        if n + max * (k as i128 - 1) < target {
             continue;
         }
 
-        if (n == 1 && ssum == 2020) || (n > 1 && find_n(nums, ssum, i + 1, n - 1, vs)) {
-            vs.push(nums[i]);
-            return true;
+        if let Some(mut vs) = find_sorted(&nums[i + 1..], target - n, k - 1) {
+            vs.insert(0, nums[i]);
+            return Some(vs);
         }
     }
 
-    false
+    None
 }
 
-pub fn part1(input: &str) -> crate::Result<i32> {
-    let nums = input
-        .lines()
-        .map(|l| l.parse::<i32>())
-        .collect::<Result<Vec<_>, _>>()?;
+/// Finds `k` numbers at distinct indices of `nums` that add up to `target`.
+///
+/// k = 2 is a single pass over a hash set, k = 3 a two-pointer scan per
+/// element of the sorted numbers, larger k recurse down to k = 2 and prune
+/// candidates whose smallest or largest possible sum misses `target`. Sums
+/// are widened to i128, so any i64 input is handled without overflow.
+pub fn find_k_sum(nums: &[i64], target: i64, k: usize) -> Option<Vec<i64>> {
+    match k {
+        0 if target == 0 => Some(Vec::new()),
+        0 => None,
+        1 => nums.iter().find(|&&n| n == target).map(|&n| vec![n]),
+        2 => find_2_sum(nums, target),
+        _ => {
+            let mut sorted = nums.to_vec();
+            sorted.sort_unstable();
+            find_sorted(&sorted, target as i128, k)
+        }
+    }
+}
+
+/// Iterator over every set of `k` distinct indices into `nums` whose numbers
+/// add up to `target`. Each item lists the indices ordered by their numbers,
+/// smallest first.
+pub struct KSums<'a> {
+    nums: &'a [i64],
+    order: Vec<usize>,
+    prefix: Vec<i128>,
+    target: i128,
+    k: usize,
+    stack: Vec<usize>,
+    sum: i128,
+    next: usize,
+    done: bool,
+}
+
+impl<'a> KSums<'a> {
+    pub fn new(nums: &'a [i64], target: i64, k: usize) -> Self {
+        let mut order: Vec<usize> = (0..nums.len()).collect();
+        order.sort_by_key(|&i| nums[i]);
+
+        let mut prefix = vec![0];
+        for &i in &order {
+            prefix.push(prefix.last().unwrap() + nums[i] as i128);
+        }
+
+        KSums {
+            nums,
+            order,
+            prefix,
+            target: target as i128,
+            k,
+            stack: Vec::new(),
+            sum: 0,
+            next: 0,
+            done: false,
+        }
+    }
+
+    fn value(&self, pos: usize) -> i128 {
+        self.nums[self.order[pos]] as i128
+    }
+
+    /// Sum of the `n` sorted numbers starting at `pos`.
+    fn range_sum(&self, pos: usize, n: usize) -> i128 {
+        self.prefix[pos + n] - self.prefix[pos]
+    }
+
+    fn pop(&mut self) {
+        if let Some(pos) = self.stack.pop() {
+            self.sum -= self.value(pos);
+            self.next = pos + 1;
+        } else {
+            self.done = true;
+        }
+    }
+}
+
+impl Iterator for KSums<'_> {
+    type Item = Vec<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let n = self.nums.len();
+        while !self.done {
+            if self.stack.len() == self.k {
+                let found = if self.sum == self.target {
+                    Some(self.stack.iter().map(|&pos| self.order[pos]).collect())
+                } else {
+                    None
+                };
+                self.pop();
+                if found.is_some() {
+                    return found;
+                }
+                continue;
+            }
 
-    let mut vs = Vec::new();
-    if find_n(&nums, 0, 0, 2, &mut vs) {
-        return Ok(vs.iter().product());
+            // The smallest sum we can still reach only grows with `next`, so
+            // once it overshoots there is nothing left at this depth
+            let rem = self.k - self.stack.len();
+            let pos = self.next;
+            if pos + rem > n || self.sum + self.range_sum(pos, rem) > self.target {
+                self.pop();
+                continue;
+            }
+
+            let max_rest = self.range_sum(n - (rem - 1), rem - 1);
+            if self.sum + self.value(pos) + max_rest < self.target {
+                self.next += 1;
+                continue;
+            }
+
+            self.stack.push(pos);
+            self.sum += self.value(pos);
+            self.next = pos + 1;
+        }
+
+        None
     }
-    Err(crate::Error::boxed(Error::InvalidInput))
 }
 
-pub fn part2(input: &str) -> crate::Result<i32> {
-    let nums = input
+/// Counts the sets of `k` distinct indices into `nums` whose numbers add up
+/// to `target`, without enumerating them.
+///
+/// `counts[j][s]` is the number of j-subsets of the numbers seen so far that
+/// sum to `s`. With non-negative numbers and a small enough target the sums
+/// are capped at `target` and kept in a dense table, otherwise every
+/// reachable sum is kept in a map. Fails with `Overflow` once any of those
+/// counts no longer fits a u64.
+pub fn count_k_sums(nums: &[i64], target: i64, k: usize) -> crate::Result<u64> {
+    const DENSE_MAX: i64 = 1 << 20;
+
+    // There are no sets larger than `nums`, and sizing the tables by such a
+    // `k` could exhaust memory
+    if k > nums.len() {
+        return Ok(0);
+    }
+
+    if (0..=DENSE_MAX).contains(&target) && nums.iter().all(|&n| n >= 0) {
+        let target = target as usize;
+        let mut counts = vec![vec![0u64; target + 1]; k + 1];
+        counts[0][0] = 1;
+        for &n in nums.iter().filter(|&&n| n <= target as i64) {
+            let n = n as usize;
+            for j in (1..=k).rev() {
+                for s in (n..=target).rev() {
+                    counts[j][s] = counts[j][s]
+                        .checked_add(counts[j - 1][s - n])
+                        .ok_or_else(|| crate::Error::boxed(Error::Overflow))?;
+                }
+            }
+        }
+        return Ok(counts[k][target]);
+    }
+
+    let mut counts = vec![HashMap::new(); k + 1];
+    counts[0].insert(0i128, 1u64);
+    for &n in nums {
+        for j in (1..=k).rev() {
+            let (lower, upper) = counts.split_at_mut(j);
+            for (s, c) in &lower[j - 1] {
+                let cnt = upper[0].entry(s + n as i128).or_insert(0u64);
+                *cnt = cnt
+                    .checked_add(*c)
+                    .ok_or_else(|| crate::Error::boxed(Error::Overflow))?;
+            }
+        }
+    }
+    Ok(*counts[k].get(&(target as i128)).unwrap_or(&0))
+}
+
+fn parse(input: &str) -> crate::Result<Vec<i64>> {
+    Ok(input
         .lines()
-        .map(|l| l.parse::<i32>())
-        .collect::<Result<Vec<_>, _>>()?;
+        .map(|l| l.parse::<i64>())
+        .collect::<Result<Vec<_>, _>>()?)
+}
+
+fn product(vs: &[i64]) -> crate::Result<i64> {
+    vs.iter()
+        .try_fold(1i64, |acc, &v| acc.checked_mul(v))
+        .ok_or_else(|| crate::Error::boxed(Error::Overflow) as _)
+}
+
+pub fn product_of_k_sum(input: &str, target: i64, k: usize) -> crate::Result<i64> {
+    let nums = parse(input)?;
+    if let Some(vs) = find_k_sum(&nums, target, k) {
+        return product(&vs);
+    }
+    Err(crate::Error::boxed(Error::NoSolution))
+}
+
+pub fn list_k_sums(input: &str, target: i64, k: usize) -> crate::Result<Vec<Vec<i64>>> {
+    let nums = parse(input)?;
+    Ok(KSums::new(&nums, target, k)
+        .map(|is| is.iter().map(|&i| nums[i]).collect())
+        .collect())
+}
+
+pub fn num_k_sums(input: &str, target: i64, k: usize) -> crate::Result<u64> {
+    let nums = parse(input)?;
+    count_k_sums(&nums, target, k)
+}
+
+pub fn part1(input: &str) -> crate::Result<i64> {
+    product_of_k_sum(input, 2020, 2)
+}
+
+pub fn part2(input: &str) -> crate::Result<i64> {
+    product_of_k_sum(input, 2020, 3)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn k_sum() {
+        let nums = [1721, 979, 366, 299, 675, 1456];
+
+        let sorted = |vs: Option<Vec<i64>>| {
+            vs.map(|mut vs| {
+                vs.sort_unstable();
+                vs
+            })
+        };
+        assert_eq!(sorted(find_k_sum(&nums, 2020, 2)), Some(vec![299, 1721]));
+        assert_eq!(
+            sorted(find_k_sum(&nums, 2020, 3)),
+            Some(vec![366, 675, 979])
+        );
+        assert_eq!(
+            sorted(find_k_sum(&nums, 2319, 4)),
+            Some(vec![299, 366, 675, 979])
+        );
+        assert_eq!(find_k_sum(&nums, 675, 1), Some(vec![675]));
+        assert_eq!(find_k_sum(&nums, 2020, 6), None);
+        assert_eq!(find_k_sum(&[1010], 2020, 2), None);
+        assert_eq!(find_k_sum(&[1010, 1010], 2020, 2), Some(vec![1010, 1010]));
+    }
+
+    #[test]
+    fn all_k_sums() {
+        let nums = [1, 2, 3, 3, 4, 5];
+
+        let mut sums: Vec<_> = KSums::new(&nums, 7, 2).collect();
+        sums.sort();
+        assert_eq!(sums, vec![vec![1, 5], vec![2, 4], vec![3, 4]]);
+        assert_eq!(count_k_sums(&nums, 7, 2).unwrap(), 3);
+
+        for k in 0..=nums.len() {
+            for target in -1..20 {
+                let brute = (0..1u32 << nums.len())
+                    .filter(|m| m.count_ones() as usize == k)
+                    .filter(|m| {
+                        let sum: i64 = (0..nums.len())
+                            .filter(|i| m & (1 << i) != 0)
+                            .map(|i| nums[i])
+                            .sum();
+                        sum == target
+                    })
+                    .count();
+                assert_eq!(KSums::new(&nums, target, k).count(), brute);
+                assert_eq!(count_k_sums(&nums, target, k).unwrap() as usize, brute);
+            }
+        }
+
+        let signed = [-3, -1, 0, 2, 2, 4];
+        assert_eq!(KSums::new(&signed, 1, 3).count(), 4);
+        assert_eq!(count_k_sums(&signed, 1, 3).unwrap(), 4);
+
+        // C(70, 35) is more than a u64 holds
+        assert!(count_k_sums(&[0; 70], 0, 35).is_err());
+        assert!(count_k_sums(&[0, -1].repeat(35), 0, 35).is_err());
+        assert_eq!(count_k_sums(&[0; 3], 0, 100_000_000).unwrap(), 0);
+        assert_eq!(count_k_sums(&[1, -1], 0, 100_000_000).unwrap(), 0);
+    }
+
+    #[test]
+    fn signed() {
+        let nums = [3000, -980, 500, 1520, -1000];
+        assert_eq!(find_k_sum(&nums, 2020, 2), Some(vec![3000, -980]));
+        assert_eq!(find_k_sum(&nums, -1980, 2), Some(vec![-980, -1000]));
+        assert_eq!(
+            find_k_sum(&nums, 1020, 3).map(|vs| vs.iter().sum()),
+            Some(1020)
+        );
+        assert_eq!(find_k_sum(&nums, 2020, 3), None);
+        assert_eq!(
+            find_k_sum(&nums, 3040, 5),
+            Some(vec![-1000, -980, 500, 1520, 3000])
+        );
+        assert_eq!(KSums::new(&nums, 1020, 3).count(), 2);
+        assert_eq!(count_k_sums(&nums, 1020, 3).unwrap(), 2);
+
+        let big = [i64::MAX, i64::MIN, -1, 1];
+        assert_eq!(find_k_sum(&big, 0, 2).map(|vs| vs.len()), Some(2));
+        assert_eq!(find_k_sum(&big, i64::MAX, 3).map(|vs| vs.len()), Some(3));
+        assert_eq!(KSums::new(&big, 0, 2).count(), 1);
+        assert_eq!(count_k_sums(&big, -1, 2).unwrap(), 1);
 
-    let mut vs = Vec::new();
-    if find_n(&nums, 0, 0, 3, &mut vs) {
-        return Ok(vs.iter().product());
+        assert!(part1("1\n2\n3").is_err());
+        assert!(part1("1\nx").is_err());
     }
-    Err(crate::Error::boxed(Error::InvalidInput))
 }
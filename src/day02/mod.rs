@@ -1,73 +1,373 @@
+use std::fmt;
 use std::str::FromStr;
 
 #[derive(Debug)]
 enum Error {
-    InvalidInput,
+    Syntax(&'static str),
+    InvalidLines(Vec<String>),
+    InvalidPolicy,
+    InvalidPositions,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Syntax(msg) => write!(f, "{}", msg),
+            Error::InvalidLines(errs) => write!(f, "{}", errs.join("\n")),
+            Error::InvalidPolicy => write!(f, "invalid policy"),
+            Error::InvalidPositions => write!(f, "invalid positions, expected char or byte"),
+        }
+    }
+}
+
+/// Whether the 1-based positions of a policy count chars or bytes of the
+/// password. In byte mode, the policy char has to start at that byte.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Positions {
+    Char,
+    Byte,
+}
+
+impl FromStr for Positions {
+    type Err = Box<dyn std::error::Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "char" => Ok(Positions::Char),
+            "byte" => Ok(Positions::Byte),
+            _ => Err(crate::Error::boxed(Error::InvalidPositions)),
+        }
+    }
 }
 
 #[derive(Debug)]
 struct Entry {
-    min: i32,
-    max: i32,
+    min: usize,
+    max: usize,
     c: char,
     pw: String,
 }
 
 impl Entry {
-    fn new(min: i32, max: i32, c: char, pw: String) -> Self {
+    fn new(min: usize, max: usize, c: char, pw: String) -> Self {
         Entry { min, max, c, pw }
     }
 
-    fn is_valid_p1(&self) -> bool {
-        let cnt = self.pw.chars().filter(|&c| c == self.c).count() as i32;
-        cnt >= self.min && cnt <= self.max
+    fn c_at_pos(&self, n: usize, positions: Positions) -> bool {
+        let i = match n.checked_sub(1) {
+            Some(i) => i,
+            None => return false,
+        };
+
+        match positions {
+            Positions::Char => self.pw.chars().nth(i) == Some(self.c),
+            Positions::Byte => self
+                .pw
+                .get(i..)
+                .map(|rest| rest.starts_with(self.c))
+                .unwrap_or(false),
+        }
+    }
+}
+
+trait Policy {
+    fn is_valid(&self, e: &Entry) -> bool;
+}
+
+/// `c` occurs between `min` and `max` times.
+struct CountRange;
+
+impl Policy for CountRange {
+    fn is_valid(&self, e: &Entry) -> bool {
+        let cnt = e.pw.chars().filter(|&c| c == e.c).count();
+        cnt >= e.min && cnt <= e.max
+    }
+}
+
+/// `c` is at exactly one of the 1-based positions `min` and `max`.
+struct ExactlyOnePosition(Positions);
+
+impl Policy for ExactlyOnePosition {
+    fn is_valid(&self, e: &Entry) -> bool {
+        e.c_at_pos(e.min, self.0) != e.c_at_pos(e.max, self.0)
+    }
+}
+
+/// `c` is at one or both of the positions `min` and `max`.
+struct AnyPosition(Positions);
+
+impl Policy for AnyPosition {
+    fn is_valid(&self, e: &Entry) -> bool {
+        e.c_at_pos(e.min, self.0) || e.c_at_pos(e.max, self.0)
     }
+}
 
-    fn is_valid_p2(&self) -> bool {
-        let c_at_pos = |s: &str, n, c| s.chars().nth(n as usize).map(|cc| cc == c).unwrap_or(false);
+/// Every char of the password is in a set like `a-z0-9_`, or in none of a
+/// negated set like `^aeiou`.
+struct CharClass {
+    ranges: Vec<(char, char)>,
+    negated: bool,
+}
 
-        c_at_pos(&self.pw, self.min - 1, self.c) != c_at_pos(&self.pw, self.max - 1, self.c)
+impl Policy for CharClass {
+    fn is_valid(&self, e: &Entry) -> bool {
+        e.pw.chars().all(|c| {
+            let in_class = self.ranges.iter().any(|&(lo, hi)| lo <= c && c <= hi);
+            in_class != self.negated
+        })
+    }
+}
+
+impl FromStr for CharClass {
+    type Err = Box<dyn std::error::Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (negated, spec) = match s.strip_prefix('^') {
+            Some(spec) => (true, spec),
+            None => (false, s),
+        };
+
+        let chars: Vec<char> = spec.chars().collect();
+        let mut ranges = Vec::new();
+        let mut i = 0;
+        while i < chars.len() {
+            if i + 2 < chars.len() && chars[i + 1] == '-' {
+                if chars[i] > chars[i + 2] {
+                    return Err(crate::Error::boxed(Error::InvalidPolicy));
+                }
+                ranges.push((chars[i], chars[i + 2]));
+                i += 3;
+            } else {
+                ranges.push((chars[i], chars[i]));
+                i += 1;
+            }
+        }
+
+        if ranges.is_empty() {
+            return Err(crate::Error::boxed(Error::InvalidPolicy));
+        }
+        Ok(CharClass { ranges, negated })
+    }
+}
+
+/// The password contains none of the given sequences.
+struct Forbidden(Vec<String>);
+
+impl Policy for Forbidden {
+    fn is_valid(&self, e: &Entry) -> bool {
+        !self.0.iter().any(|seq| e.pw.contains(seq.as_str()))
+    }
+}
+
+/// A policy as selected on the command line: `count`, `exactly-one`,
+/// `any-position`, `class=<set>` or `forbid=<seq>|<seq>...`.
+struct NamedPolicy {
+    name: String,
+    policy: Box<dyn Policy>,
+}
+
+impl NamedPolicy {
+    fn new(s: &str, positions: Positions) -> crate::Result<Self> {
+        let mut parts = s.splitn(2, '=');
+        let kind = parts.next().unwrap_or("");
+        let arg = parts.next();
+
+        let policy: Box<dyn Policy> = match (kind, arg) {
+            ("count", None) => Box::new(CountRange),
+            ("exactly-one", None) => Box::new(ExactlyOnePosition(positions)),
+            ("any-position", None) => Box::new(AnyPosition(positions)),
+            ("class", Some(spec)) => Box::new(spec.parse::<CharClass>()?),
+            ("forbid", Some(seqs)) if seqs.split('|').all(|seq| !seq.is_empty()) => {
+                Box::new(Forbidden(seqs.split('|').map(String::from).collect()))
+            }
+            _ => return Err(crate::Error::boxed(Error::InvalidPolicy)),
+        };
+
+        Ok(NamedPolicy {
+            name: s.to_string(),
+            policy,
+        })
     }
 }
 
 impl FromStr for Entry {
     type Err = Box<dyn std::error::Error>;
 
+    /// Parses `<min>-<max> <c>: <pw>`, where min and max are plain decimal
+    /// numbers and the password is non-empty and contains no whitespace.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut chars = s.chars();
-        let min = chars
-            .by_ref()
-            .take_while(|c| c.is_digit(10))
-            .collect::<String>()
-            .parse::<i32>()?;
-        let max = chars
-            .by_ref()
-            .take_while(|c| c.is_digit(10))
-            .collect::<String>()
-            .parse::<i32>()?;
-        let c = chars
-            .next()
-            .ok_or_else(|| crate::Error::boxed(Error::InvalidInput))?;
-        let pw = chars.skip(1).collect::<String>().trim().to_string();
-
-        Ok(Entry::new(min, max, c, pw))
+        let syntax = |msg| crate::Error::boxed(Error::Syntax(msg));
+        let number = |n: &str| {
+            if n.is_empty() || !n.chars().all(|c| c.is_ascii_digit()) {
+                return Err(syntax("expected a decimal number"));
+            }
+            n.parse::<usize>()
+                .map_err(|_| syntax("number out of range"))
+        };
+
+        let (range, rest) = s
+            .split_once(' ')
+            .ok_or_else(|| syntax("expected '<min>-<max> <char>: <password>'"))?;
+        let (min, max) = range
+            .split_once('-')
+            .ok_or_else(|| syntax("expected '-' between min and max"))?;
+        let (min, max) = (number(min)?, number(max)?);
+        if min > max {
+            return Err(syntax("min is larger than max"));
+        }
+
+        let (c, pw) = rest
+            .split_once(": ")
+            .ok_or_else(|| syntax("expected ': ' after the policy char"))?;
+        let mut cs = c.chars();
+        let c = match (cs.next(), cs.next()) {
+            (Some(c), None) => c,
+            _ => return Err(syntax("expected a single policy char")),
+        };
+
+        if pw.is_empty() || pw.chars().any(char::is_whitespace) {
+            return Err(syntax("expected a non-empty password without whitespace"));
+        }
+
+        Ok(Entry::new(min, max, c, pw.to_string()))
     }
 }
 
-pub fn part1(input: &str) -> crate::Result<i32> {
-    let es = input
-        .lines()
-        .map(Entry::from_str)
+/// Parses all lines, reporting every malformed one with its line number.
+fn parse(input: &str) -> crate::Result<Vec<Entry>> {
+    let mut es = Vec::new();
+    let mut errs = Vec::new();
+    for (n, l) in input.lines().enumerate() {
+        match l.parse() {
+            Ok(e) => es.push(e),
+            Err(err) => errs.push(format!("line {}: {}: '{}'", n + 1, err, l)),
+        }
+    }
+
+    if !errs.is_empty() {
+        return Err(crate::Error::boxed(Error::InvalidLines(errs)));
+    }
+    Ok(es)
+}
+
+/// Lists every password with the comma separated `policies` it passes,
+/// followed by the number of passwords passing each policy.
+pub fn report(input: &str, policies: &str, positions: Positions) -> crate::Result<String> {
+    let es = parse(input)?;
+    let policies = policies
+        .split(',')
+        .map(|p| NamedPolicy::new(p, positions))
         .collect::<Result<Vec<_>, _>>()?;
 
-    Ok(es.iter().filter(|e| e.is_valid_p1()).count() as i32)
+    let mut out = String::new();
+    let mut cnts = vec![0; policies.len()];
+    for e in &es {
+        let mut passed = Vec::new();
+        for (p, cnt) in policies.iter().zip(cnts.iter_mut()) {
+            if p.policy.is_valid(e) {
+                passed.push(p.name.as_str());
+                *cnt += 1;
+            }
+        }
+        out += &format!(
+            "{}-{} {}: {} -> {}\n",
+            e.min,
+            e.max,
+            e.c,
+            e.pw,
+            passed.join(", ")
+        );
+    }
+
+    for (p, cnt) in policies.iter().zip(cnts) {
+        out += &format!("{}: {}/{}\n", p.name, cnt, es.len());
+    }
+    Ok(out)
+}
+
+pub fn part1(input: &str) -> crate::Result<i32> {
+    let es = parse(input)?;
+    Ok(es.iter().filter(|e| CountRange.is_valid(e)).count() as i32)
+}
+
+pub fn part2_with(input: &str, positions: Positions) -> crate::Result<i32> {
+    let es = parse(input)?;
+    let policy = ExactlyOnePosition(positions);
+    Ok(es.iter().filter(|e| policy.is_valid(e)).count() as i32)
 }
 
 pub fn part2(input: &str) -> crate::Result<i32> {
-    let es = input
-        .lines()
-        .map(Entry::from_str)
-        .collect::<Result<Vec<_>, _>>()?;
+    part2_with(input, Positions::Char)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn policies() {
+        let inp = "1-3 a: abcde
+1-3 b: cdefg
+2-9 c: ccccccccc";
+        assert_eq!(part1(inp).unwrap(), 2);
+        assert_eq!(part2(inp).unwrap(), 1);
 
-    Ok(es.iter().filter(|e| e.is_valid_p2()).count() as i32)
+        let rep = report(
+            inp,
+            "any-position,class=^a-b,forbid=cc|xyz",
+            Positions::Char,
+        )
+        .unwrap();
+        let lines: Vec<_> = rep.lines().collect();
+        assert_eq!(lines[0], "1-3 a: abcde -> any-position, forbid=cc|xyz");
+        assert_eq!(lines[1], "1-3 b: cdefg -> class=^a-b, forbid=cc|xyz");
+        assert_eq!(lines[2], "2-9 c: ccccccccc -> any-position, class=^a-b");
+        assert_eq!(
+            lines[3..],
+            ["any-position: 2/3", "class=^a-b: 2/3", "forbid=cc|xyz: 2/3"]
+        );
+
+        assert!(report(inp, "class=z-a", Positions::Char).is_err());
+        assert!(report(inp, "forbid=", Positions::Char).is_err());
+        assert!(report(inp, "nope", Positions::Char).is_err());
+    }
+
+    #[test]
+    fn strict_parse() {
+        for bad in &[
+            "1x3 a: abc",
+            "1-3 a abc",
+            "1-3 ab: abc",
+            "-3 a: abc",
+            "1- a: abc",
+            "3-1 a: abc",
+            "1-3 a: ",
+            "1-3 a: a c",
+            "1-99999999999999999999999 a: abc",
+        ] {
+            assert!(bad.parse::<Entry>().is_err(), "{}", bad);
+        }
+
+        let err = parse("1-3 a: abc\n1x3 a: abc\n2-4 b: bb\n1-3 a").unwrap_err();
+        let msg = err.to_string();
+        assert!(
+            msg.contains("line 2:") && msg.contains("line 4:"),
+            "{}",
+            msg
+        );
+        assert!(
+            !msg.contains("line 1:") && !msg.contains("line 3:"),
+            "{}",
+            msg
+        );
+
+        // 'é' is two bytes, so 'b' is the second char but starts at byte 3
+        let inp = "0-3 b: ébc\n2-4 b: ébc";
+        assert_eq!(part2_with(inp, Positions::Char).unwrap(), 1);
+        assert_eq!(part2_with(inp, Positions::Byte).unwrap(), 1);
+        let e: Entry = "2-4 b: ébc".parse().unwrap();
+        assert!(e.c_at_pos(2, Positions::Char) && !e.c_at_pos(3, Positions::Char));
+        assert!(e.c_at_pos(3, Positions::Byte) && !e.c_at_pos(4, Positions::Byte));
+        assert!(!e.c_at_pos(2, Positions::Byte));
+    }
 }
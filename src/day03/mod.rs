@@ -1,46 +1,138 @@
+use std::collections::HashSet;
+use std::fmt;
+
 #[derive(Debug)]
 enum Error {
     InvalidInput,
 }
 
-fn count_trees(grid: &[Vec<char>], slope: (usize, usize)) -> crate::Result<i32> {
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidInput => write!(f, "invalid input"),
+        }
+    }
+}
+
+/// Width and height of a non-empty rectangular grid, as long as `slope`
+/// actually makes it to the bottom.
+fn dims(grid: &[Vec<char>], slope: (usize, usize)) -> crate::Result<(usize, usize)> {
     let h = grid.len();
-    if h == 0 {
+    if h == 0 || slope.1 == 0 {
         return Err(crate::Error::boxed(Error::InvalidInput));
     }
     let w = grid[0].len();
-    if w == 0 {
+    if w == 0 || grid.iter().any(|row| row.len() != w) {
         return Err(crate::Error::boxed(Error::InvalidInput));
     }
 
-    let mut pos = (0, 0);
-    let mut cnt = 0;
-    while pos.1 < h {
-        if grid[pos.1][pos.0] == '#' {
-            cnt += 1;
-        }
+    Ok((w, h))
+}
+
+/// Positions visited going down `slope` from the top left, before wrapping
+/// around the width of the map.
+fn path(h: usize, slope: (usize, usize)) -> impl Iterator<Item = (usize, usize)> {
+    (0..)
+        .map(move |i| (i * slope.0, i * slope.1))
+        .take_while(move |p| p.1 < h)
+}
 
-        pos.0 = (pos.0 + slope.0) % w;
-        pos.1 += slope.1;
+fn count_trees(grid: &[Vec<char>], slope: (usize, usize)) -> crate::Result<i32> {
+    let (w, h) = dims(grid, slope)?;
+    Ok(path(h, slope)
+        .filter(|&(x, y)| grid[y][x % w] == '#')
+        .count() as i32)
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
     }
+}
 
-    Ok(cnt)
+/// All slopes going right `0..=bound` for every `1..=bound` down, in lowest
+/// terms. Stepping by a reduced slope visits every grid point on its line of
+/// sight, e.g. right 1 down 3 only touches every third row, and right 2 down
+/// 2 is the same line as right 1 down 1.
+fn slopes(bound: usize) -> impl Iterator<Item = (usize, usize)> {
+    (1..=bound)
+        .flat_map(move |down| (0..=bound).map(move |right| (right, down)))
+        .filter(|&(right, down)| gcd(right, down) == 1)
 }
 
-pub fn part1(input: &str) -> crate::Result<i32> {
-    let grid: Vec<Vec<char>> = input
+fn parse(input: &str) -> Vec<Vec<char>> {
+    input
         .lines()
         .map(|s| s.chars().collect::<Vec<_>>())
-        .collect();
+        .collect()
+}
+
+/// Returns the slopes up to `bound` hitting the fewest and the most trees,
+/// each with its tree count. Ties go to the slope going down the least, then
+/// right the least.
+pub fn search_slopes(input: &str, bound: usize) -> crate::Result<[((usize, usize), i32); 2]> {
+    let grid = parse(input);
+
+    let mut fewest: Option<((usize, usize), i32)> = None;
+    let mut most: Option<((usize, usize), i32)> = None;
+    for slope in slopes(bound) {
+        let cnt = count_trees(&grid, slope)?;
+        if fewest.map(|(_, c)| cnt < c).unwrap_or(true) {
+            fewest = Some((slope, cnt));
+        }
+        if most.map(|(_, c)| cnt > c).unwrap_or(true) {
+            most = Some((slope, cnt));
+        }
+    }
+
+    match (fewest, most) {
+        (Some(fewest), Some(most)) => Ok([fewest, most]),
+        _ => Err(crate::Error::boxed(Error::InvalidInput)),
+    }
+}
+
+/// Parses a slope given as `<right>,<down>`.
+pub fn parse_slope(s: &str) -> crate::Result<(usize, usize)> {
+    let (right, down) = s
+        .split_once(',')
+        .ok_or_else(|| crate::Error::boxed(Error::InvalidInput))?;
+    Ok((right.trim().parse()?, down.trim().parse()?))
+}
+
+/// Draws the map with every position the toboggan passes on `slope` marked
+/// `O` on open ground and `X` on a tree, repeating the map to the right as
+/// often as the path wraps around.
+pub fn render_path(input: &str, slope: (usize, usize)) -> crate::Result<String> {
+    let grid = parse(input);
+    let (w, h) = dims(&grid, slope)?;
+
+    let visited: HashSet<(usize, usize)> = path(h, slope).collect();
+    let repeats = visited.iter().map(|p| p.0 / w).max().unwrap_or(0) + 1;
+
+    let mut out = String::new();
+    for (y, row) in grid.iter().enumerate() {
+        for x in 0..w * repeats {
+            let c = row[x % w];
+            out.push(match (visited.contains(&(x, y)), c) {
+                (true, '#') => 'X',
+                (true, _) => 'O',
+                _ => c,
+            });
+        }
+        out.push('\n');
+    }
+    Ok(out)
+}
 
-    Ok(count_trees(&grid, (3, 1))?)
+pub fn part1(input: &str) -> crate::Result<i32> {
+    let grid = parse(input);
+    count_trees(&grid, (3, 1))
 }
 
 pub fn part2(input: &str) -> crate::Result<i64> {
-    let grid: Vec<Vec<char>> = input
-        .lines()
-        .map(|s| s.chars().collect::<Vec<_>>())
-        .collect();
+    let grid = parse(input);
     let slopes = [(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)];
 
     let cnts = slopes
@@ -50,3 +142,54 @@ pub fn part2(input: &str) -> crate::Result<i64> {
 
     Ok(cnts.iter().map(|&i| i as i64).product())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EX: &str = "..##.......
+#...#...#..
+.#....#..#.
+..#.#...#.#
+.#...##..#.
+..#.##.....
+.#.#.#....#
+.#........#
+#.##...#...
+#...##....#
+.#..#...#.#";
+
+    #[test]
+    fn search() {
+        assert_eq!(part2(EX).unwrap(), 336);
+
+        let all: Vec<_> = slopes(3).collect();
+        assert!(all.contains(&(1, 3)) && all.contains(&(0, 1)));
+        assert!(!all.contains(&(2, 2)) && !all.contains(&(0, 2)));
+        assert_eq!(all.len(), 8);
+
+        let grid = parse(EX);
+        assert_eq!(count_trees(&grid, (1, 3)).unwrap(), 0);
+
+        let [fewest, most] = search_slopes(EX, 3).unwrap();
+        assert_eq!(fewest, ((1, 3), 0));
+        assert_eq!(most, ((3, 1), 7));
+        assert!(search_slopes(EX, 0).is_err());
+    }
+
+    #[test]
+    fn render() {
+        let out = render_path(EX, (3, 1)).unwrap();
+        let lines: Vec<_> = out.lines().collect();
+        assert_eq!(lines.len(), 11);
+        assert_eq!(lines[0], "O.##.........##.........##.......");
+        assert_eq!(lines[1], "#..O#...#..#...#...#..#...#...#..");
+        assert_eq!(lines[2], ".#....X..#..#....#..#..#....#..#.");
+        assert_eq!(lines[10], ".#..#...#.#.#..#...#.#.#..#...X.#");
+        assert_eq!(out.matches('X').count(), 7);
+
+        assert_eq!(parse_slope("1, 3").unwrap(), (1, 3));
+        assert!(render_path(EX, (1, 0)).is_err());
+        assert!(render_path("..\n.", (1, 1)).is_err());
+    }
+}
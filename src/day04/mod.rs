@@ -1,129 +1,939 @@
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::ops::RangeInclusive;
 use std::str::FromStr;
 
 #[derive(Debug)]
 enum Error {
-    InvalidInput,
+    MalformedField(String),
+    DuplicateKey(String),
+    InvalidValue(String, String),
+    InvalidRecord(usize, Box<Error>),
+    InvalidPattern,
+    InvalidFormat,
+    MalformedSchema(usize),
 }
 
-#[derive(Debug, Default)]
-struct Passport {
-    byr: String,
-    iyr: String,
-    eyr: String,
-    hgt: String,
-    hcl: String,
-    ecl: String,
-    pid: String,
-    cid: String,
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::MalformedField(field) => write!(f, "malformed field '{}'", field),
+            Error::DuplicateKey(key) => write!(f, "duplicate key '{}'", key),
+            Error::InvalidValue(key, value) => write!(f, "invalid value '{}' for {}", value, key),
+            Error::InvalidRecord(n, err) => write!(f, "record {}: {}", n, err),
+            Error::InvalidPattern => write!(f, "invalid pattern"),
+            Error::InvalidFormat => write!(f, "invalid format, expected text or json"),
+            Error::MalformedSchema(n) => write!(f, "malformed schema on line {}", n),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Atom {
+    Any,
+    Char(char),
+    Class(Vec<(char, char)>, bool),
+}
+
+impl Atom {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            Atom::Any => true,
+            Atom::Char(cc) => *cc == c,
+            Atom::Class(ranges, negated) => {
+                ranges.iter().any(|&(lo, hi)| lo <= c && c <= hi) != *negated
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Piece {
+    atom: Atom,
+    min: usize,
+    max: usize,
+}
+
+/// A regex-like pattern that has to match a whole value. Supports literals,
+/// `.`, classes like `[a-f0-9]` or `[^0]`, `\d`, `\w`, escapes like `\.` and
+/// the quantifiers `?`, `*`, `+`, `{n}`, `{n,}` and `{n,m}`.
+#[derive(Debug)]
+struct Pattern {
+    src: String,
+    pieces: Vec<Piece>,
+}
+
+impl Pattern {
+    fn matches(&self, s: &str) -> bool {
+        let cs: Vec<char> = s.chars().collect();
+        self.matches_from(0, &cs)
+    }
+
+    fn matches_from(&self, piece: usize, cs: &[char]) -> bool {
+        let p = match self.pieces.get(piece) {
+            Some(p) => p,
+            None => return cs.is_empty(),
+        };
+
+        let n = cs
+            .iter()
+            .take(p.max)
+            .take_while(|&&c| p.atom.matches(c))
+            .count();
+        (p.min..=n)
+            .rev()
+            .any(|k| self.matches_from(piece + 1, &cs[k..]))
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = Box<dyn std::error::Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || crate::Error::boxed(Error::InvalidPattern);
+
+        let mut cs = s.chars().peekable();
+        let mut pieces = Vec::new();
+        while let Some(c) = cs.next() {
+            let atom = match c {
+                '.' => Atom::Any,
+                '\\' => match cs.next().ok_or_else(err)? {
+                    'd' => Atom::Class(vec![('0', '9')], false),
+                    'w' => Atom::Class(vec![('a', 'z'), ('A', 'Z'), ('0', '9'), ('_', '_')], false),
+                    c => Atom::Char(c),
+                },
+                '[' => {
+                    let negated = cs.peek() == Some(&'^');
+                    if negated {
+                        cs.next();
+                    }
+
+                    let mut ranges = Vec::new();
+                    loop {
+                        let lo = match cs.next().ok_or_else(err)? {
+                            ']' if !ranges.is_empty() => break,
+                            '\\' => cs.next().ok_or_else(err)?,
+                            c => c,
+                        };
+                        let hi = if cs.peek() == Some(&'-') {
+                            cs.next();
+                            cs.next().filter(|&c| c != ']').ok_or_else(err)?
+                        } else {
+                            lo
+                        };
+                        if lo > hi {
+                            return Err(err());
+                        }
+                        ranges.push((lo, hi));
+                    }
+                    Atom::Class(ranges, negated)
+                }
+                '?' | '*' | '+' | '{' | ']' => return Err(err()),
+                c => Atom::Char(c),
+            };
+
+            let quantifier = match cs.peek() {
+                Some('?') | Some('*') | Some('+') | Some('{') => cs.next(),
+                _ => None,
+            };
+            let (min, max) = match quantifier {
+                Some('?') => (0, 1),
+                Some('*') => (0, usize::MAX),
+                Some('+') => (1, usize::MAX),
+                Some('{') => {
+                    let mut spec = String::new();
+                    loop {
+                        match cs.next() {
+                            Some('}') => break,
+                            Some(c) => spec.push(c),
+                            None => return Err(err()),
+                        }
+                    }
+                    match spec.split_once(',') {
+                        None => {
+                            let n = spec.parse()?;
+                            (n, n)
+                        }
+                        Some((min, "")) => (min.parse()?, usize::MAX),
+                        Some((min, max)) => (min.parse()?, max.parse()?),
+                    }
+                }
+                _ => (1, 1),
+            };
+            if min > max {
+                return Err(err());
+            }
+
+            pieces.push(Piece { atom, min, max });
+        }
+
+        Ok(Pattern {
+            src: s.to_string(),
+            pieces,
+        })
+    }
 }
 
-impl Passport {
-    fn is_valid_p1(&self) -> bool {
-        !self.byr.is_empty()
-            && !self.iyr.is_empty()
-            && !self.eyr.is_empty()
-            && !self.hgt.is_empty()
-            && !self.hcl.is_empty()
-            && !self.ecl.is_empty()
-            && !self.pid.is_empty()
+fn parse_range(s: &str) -> Option<RangeInclusive<i64>> {
+    let (min, max) = s.split_once("..")?;
+    Some(min.parse().ok()?..=max.parse().ok()?)
+}
+
+#[derive(Debug)]
+enum Rule {
+    Int(RangeInclusive<i64>),
+    Units(Vec<(String, RangeInclusive<i64>)>),
+    Pattern(Pattern),
+    Enum(Vec<String>),
+}
+
+impl Rule {
+    fn parse(kind: &str, arg: &str) -> Option<Rule> {
+        let rule = match kind {
+            "int" => Rule::Int(parse_range(arg)?),
+            "units" => Rule::Units(
+                arg.split(',')
+                    .map(|u| {
+                        let (unit, range) = u.split_once(':')?;
+                        Some((unit.to_string(), parse_range(range)?))
+                    })
+                    .collect::<Option<_>>()?,
+            ),
+            "pattern" => Rule::Pattern(arg.parse().ok()?),
+            "enum" => Rule::Enum(arg.split(',').map(String::from).collect()),
+            _ => return None,
+        };
+        Some(rule)
     }
 
-    fn is_valid_p2(&self) -> bool {
-        let in_range =
-            |v: &str, min, max| matches!(v.parse::<i32>().map(|i| i >= min && i <= max), Ok(true));
+    fn is_valid(&self, v: &str) -> bool {
+        // Plain decimal integers only, `parse` would also take a leading `+`
+        let is_int = |s: &str| {
+            let digits = s.strip_prefix('-').unwrap_or(s);
+            !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit())
+        };
+        let in_range = |s: &str, r: &RangeInclusive<i64>| {
+            is_int(s) && matches!(s.parse::<i64>().map(|i| r.contains(&i)), Ok(true))
+        };
 
-        if !in_range(&self.byr, 1920, 2002) {
-            return false;
+        match self {
+            Rule::Int(r) => in_range(v, r),
+            Rule::Units(units) => units.iter().any(|(unit, r)| {
+                v.strip_suffix(unit.as_str())
+                    .map(|n| in_range(n, r))
+                    .unwrap_or(false)
+            }),
+            Rule::Pattern(p) => p.matches(v),
+            Rule::Enum(vs) => vs.iter().any(|e| e == v),
         }
+    }
+}
 
-        if !in_range(&self.iyr, 2010, 2020) {
-            return false;
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let range = |r: &RangeInclusive<i64>| format!("{}..{}", r.start(), r.end());
+        match self {
+            Rule::Int(r) => write!(f, "int {}", range(r)),
+            Rule::Units(units) => {
+                let units: Vec<_> = units
+                    .iter()
+                    .map(|(unit, r)| format!("{}:{}", unit, range(r)))
+                    .collect();
+                write!(f, "units {}", units.join(","))
+            }
+            Rule::Pattern(p) => write!(f, "pattern {}", p.src),
+            Rule::Enum(vs) => write!(f, "enum {}", vs.join(",")),
         }
+    }
+}
+
+#[derive(Debug)]
+enum Violation<'a> {
+    Missing(&'a str),
+    Invalid {
+        field: &'a str,
+        value: &'a str,
+        rule: &'a Rule,
+    },
+}
 
-        if !in_range(&self.eyr, 2020, 2030) {
-            return false;
+impl Violation<'_> {
+    /// What went wrong, without the offending value, for aggregating.
+    fn reason(&self) -> String {
+        match self {
+            Violation::Missing(field) => format!("missing {}", field),
+            Violation::Invalid { field, rule, .. } => format!("{}: {}", field, rule),
         }
+    }
+}
 
-        if let Some((i, _)) = self.hgt.chars().enumerate().find(|(_, c)| !c.is_numeric()) {
-            if !match &self.hgt[i..] {
-                "cm" => in_range(&self.hgt[..i], 150, 193),
-                "in" => in_range(&self.hgt[..i], 59, 76),
-                _ => false,
-            } {
-                return false;
+#[derive(Debug)]
+struct FieldSpec {
+    name: String,
+    required: bool,
+    rules: Vec<Rule>,
+}
+
+/// Passport field rules, one field per line, see `input/day04_schema`.
+#[derive(Debug)]
+struct Schema(Vec<FieldSpec>);
+
+impl Schema {
+    fn has_required(&self, p: &Record) -> bool {
+        self.0
+            .iter()
+            .all(|f| !f.required || p.get(&f.name).is_some())
+    }
+
+    /// Every missing required field and every rule a present field breaks,
+    /// in schema order.
+    fn violations<'a>(&'a self, p: &'a Record) -> Vec<Violation<'a>> {
+        let mut vs = Vec::new();
+        for f in &self.0 {
+            match p.get(&f.name) {
+                Some(value) => {
+                    vs.extend(f.rules.iter().filter(|r| !r.is_valid(value)).map(|rule| {
+                        Violation::Invalid {
+                            field: &f.name,
+                            value,
+                            rule,
+                        }
+                    }))
+                }
+                None if f.required => vs.push(Violation::Missing(&f.name)),
+                None => (),
             }
-        } else {
-            return false;
         }
+        vs
+    }
 
-        {
-            if self.hcl.len() != 7 {
-                return false;
+    fn is_valid(&self, p: &Record) -> bool {
+        self.violations(p).is_empty()
+    }
+}
+
+impl FromStr for Schema {
+    type Err = Box<dyn std::error::Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut fields = Vec::new();
+        for (n, l) in s.lines().enumerate() {
+            let err = || crate::Error::boxed(Error::MalformedSchema(n + 1));
+
+            let l = l.trim();
+            if l.is_empty() || l.starts_with('#') {
+                continue;
             }
 
-            let mut chars = self.hcl.chars();
-            if chars.next() != Some('#') {
-                return false;
+            let mut words = l.split_whitespace();
+            let name = words.next().ok_or_else(err)?.to_string();
+            let required = match words.next() {
+                Some("required") => true,
+                Some("optional") => false,
+                _ => return Err(err()),
+            };
+
+            let mut rules = Vec::new();
+            while let Some(kind) = words.next() {
+                let arg = words.next().ok_or_else(err)?;
+                rules.push(Rule::parse(kind, arg).ok_or_else(err)?);
+            }
+
+            fields.push(FieldSpec {
+                name,
+                required,
+                rules,
+            });
+        }
+
+        Ok(Schema(fields))
+    }
+}
+
+/// The raw `key:value` pairs of one passport, in input order. Part 1, part 2,
+/// schemas and reports all check these, as a schema may name any field and
+/// its rules apply to the text as given, and a value of the wrong shape makes
+/// a passport invalid rather than unreadable. `Passport` is a typed view
+/// parsed from a record on top of that, not a replacement for it.
+#[derive(Debug, Default)]
+struct Record {
+    fields: Vec<(String, String)>,
+}
+
+impl Record {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+impl FromStr for Record {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut r = Record::default();
+        for t in s.split_whitespace() {
+            let (k, v) = match t.split_once(':') {
+                Some((k, v)) if !k.is_empty() && !v.contains(':') => (k, v),
+                _ => return Err(Error::MalformedField(t.to_string())),
+            };
+            // An empty value counts as if the field wasn't there at all
+            if v.is_empty() {
+                continue;
+            }
+            if r.get(k).is_some() {
+                return Err(Error::DuplicateKey(k.to_string()));
             }
 
-            if !chars.all(|c| c.is_ascii_hexdigit()) {
-                return false;
+            r.fields.push((k.to_string(), v.to_string()));
+        }
+        Ok(r)
+    }
+}
+
+/// Items along with the number of the record they came from.
+type Numbered<T> = Vec<(usize, T)>;
+
+/// Splits `s` into records on blank lines and converts each with `f`, along
+/// with its number counting from 1. When `lenient`, records that fail are
+/// skipped with a warning each instead of failing the whole batch.
+fn parse_records<T>(
+    s: &str,
+    lenient: bool,
+    f: impl Fn(&str) -> Result<T, Error>,
+) -> crate::Result<(Numbered<T>, Vec<String>)> {
+    let mut ps = Vec::new();
+    let mut warnings = Vec::new();
+    for (n, entry) in s.split("\n\n").enumerate() {
+        match f(entry) {
+            Ok(p) => ps.push((n + 1, p)),
+            Err(e) if lenient => warnings.push(format!("skipped record {}: {}", n + 1, e)),
+            Err(e) => {
+                return Err(crate::Error::boxed(Error::InvalidRecord(
+                    n + 1,
+                    Box::new(e),
+                )))
             }
         }
+    }
+    Ok((ps, warnings))
+}
+
+/// The records of a batch, with a warning for every record skipped when
+/// parsing leniently.
+struct Collection {
+    records: Numbered<Record>,
+    warnings: Vec<String>,
+}
+
+impl Collection {
+    fn parse(s: &str, lenient: bool) -> crate::Result<Self> {
+        let (records, warnings) = parse_records(s, lenient, str::parse)?;
+        Ok(Collection { records, warnings })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Height {
+    Cm(u16),
+    In(u16),
+}
 
-        const ECLS: [&str; 7] = ["amb", "blu", "brn", "gry", "grn", "hzl", "oth"];
-        if !ECLS.contains(&self.ecl.as_str()) {
-            return false;
+impl FromStr for Height {
+    type Err = Box<dyn std::error::Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(v) = s.strip_suffix("cm") {
+            return Ok(Height::Cm(v.parse()?));
+        }
+        if let Some(v) = s.strip_suffix("in") {
+            return Ok(Height::In(v.parse()?));
         }
+        Err(crate::Error::boxed(Error::InvalidValue(
+            "hgt".to_string(),
+            s.to_string(),
+        )))
+    }
+}
 
-        if self.pid.len() != 9 || !self.pid.chars().all(|c| c.is_numeric()) {
-            return false;
+impl fmt::Display for Height {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Height::Cm(v) => write!(f, "{}cm", v),
+            Height::In(v) => write!(f, "{}in", v),
         }
+    }
+}
+
+/// A `#rrggbb` hair color.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Color(pub u8, pub u8, pub u8);
+
+impl FromStr for Color {
+    type Err = Box<dyn std::error::Error>;
 
-        true
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = match s.strip_prefix('#') {
+            Some(hex) if hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit()) => hex,
+            _ => {
+                return Err(crate::Error::boxed(Error::InvalidValue(
+                    "hcl".to_string(),
+                    s.to_string(),
+                )))
+            }
+        };
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16);
+        Ok(Color(channel(0)?, channel(2)?, channel(4)?))
     }
 }
 
-struct Collection(Vec<Passport>);
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.0, self.1, self.2)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EyeColor {
+    Amb,
+    Blu,
+    Brn,
+    Gry,
+    Grn,
+    Hzl,
+    Oth,
+}
+
+const EYE_COLORS: [(&str, EyeColor); 7] = [
+    ("amb", EyeColor::Amb),
+    ("blu", EyeColor::Blu),
+    ("brn", EyeColor::Brn),
+    ("gry", EyeColor::Gry),
+    ("grn", EyeColor::Grn),
+    ("hzl", EyeColor::Hzl),
+    ("oth", EyeColor::Oth),
+];
 
-impl FromStr for Collection {
+impl FromStr for EyeColor {
     type Err = Box<dyn std::error::Error>;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut ps = Vec::new();
+        if let Some(&(_, c)) = EYE_COLORS.iter().find(|(name, _)| *name == s) {
+            return Ok(c);
+        }
+        Err(crate::Error::boxed(Error::InvalidValue(
+            "ecl".to_string(),
+            s.to_string(),
+        )))
+    }
+}
+
+impl fmt::Display for EyeColor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (name, _) = EYE_COLORS.iter().find(|(_, c)| c == self).unwrap();
+        write!(f, "{}", name)
+    }
+}
+
+/// A passport with the well known fields parsed into their types, as a view
+/// of a `Record` for callers that want typed values. Only the shape of each
+/// value is checked, not whether it is in range, that is up to the schema,
+/// which still validates the record itself.
+#[derive(Debug, Default, PartialEq)]
+pub struct Passport {
+    pub byr: Option<u16>,
+    pub iyr: Option<u16>,
+    pub eyr: Option<u16>,
+    pub hgt: Option<Height>,
+    pub hcl: Option<Color>,
+    pub ecl: Option<EyeColor>,
+    pub pid: Option<String>,
+    pub cid: Option<String>,
+    /// Any other fields, in input order.
+    pub unknown: Vec<(String, String)>,
+}
+
+impl TryFrom<&Record> for Passport {
+    type Error = Error;
+
+    fn try_from(r: &Record) -> Result<Self, Self::Error> {
+        fn typed<T: FromStr>(k: &str, v: &str) -> Result<Option<T>, Error> {
+            match v.parse() {
+                Ok(v) => Ok(Some(v)),
+                Err(_) => Err(Error::InvalidValue(k.to_string(), v.to_string())),
+            }
+        }
+
         let mut p = Passport::default();
-        for entry in s.split("\n\n") {
-            for t in entry.split_whitespace() {
-                let kv = t.split(':').collect::<Vec<_>>();
-                if kv.len() != 2 {
-                    return Err(crate::Error::boxed(Error::InvalidInput));
-                }
+        for (k, v) in &r.fields {
+            match k.as_str() {
+                "byr" => p.byr = typed(k, v)?,
+                "iyr" => p.iyr = typed(k, v)?,
+                "eyr" => p.eyr = typed(k, v)?,
+                "hgt" => p.hgt = typed(k, v)?,
+                "hcl" => p.hcl = typed(k, v)?,
+                "ecl" => p.ecl = typed(k, v)?,
+                "pid" => p.pid = Some(v.clone()),
+                "cid" => p.cid = Some(v.clone()),
+                _ => p.unknown.push((k.clone(), v.clone())),
+            }
+        }
+        Ok(p)
+    }
+}
+
+impl fmt::Display for Passport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut fields: Vec<String> = Vec::new();
+        let mut field = |k: &str, v: Option<String>| {
+            if let Some(v) = v {
+                fields.push(format!("{}:{}", k, v));
+            }
+        };
+        field("byr", self.byr.map(|v| v.to_string()));
+        field("iyr", self.iyr.map(|v| v.to_string()));
+        field("eyr", self.eyr.map(|v| v.to_string()));
+        field("hgt", self.hgt.map(|v| v.to_string()));
+        field("hcl", self.hcl.map(|v| v.to_string()));
+        field("ecl", self.ecl.map(|v| v.to_string()));
+        field("pid", self.pid.clone());
+        field("cid", self.cid.clone());
+        for (k, v) in &self.unknown {
+            field(k, Some(v.clone()));
+        }
+        write!(f, "{}", fields.join(" "))
+    }
+}
+
+/// Parses every passport in `input` into a typed `Passport`. When `lenient`,
+/// records that are malformed or have a value of the wrong shape are skipped
+/// and listed as warnings, otherwise the first one is an error.
+pub fn passports(input: &str, lenient: bool) -> crate::Result<(Vec<Passport>, Vec<String>)> {
+    let (ps, warnings) = parse_records(input, lenient, |s| {
+        Passport::try_from(&s.parse::<Record>()?)
+    })?;
+    Ok((ps.into_iter().map(|(_, p)| p).collect(), warnings))
+}
+
+pub const DEFAULT_SCHEMA: &str = include_str!("../../input/day04_schema");
+
+/// Counts the passports in `input` that have all fields `schema` requires,
+/// with the warnings for records skipped when `lenient`.
+pub fn count_complete(
+    input: &str,
+    schema: &str,
+    lenient: bool,
+) -> crate::Result<(i32, Vec<String>)> {
+    let schema = Schema::from_str(schema)?;
+    let ps = Collection::parse(input, lenient)?;
+    let cnt = ps
+        .records
+        .iter()
+        .filter(|(_, p)| schema.has_required(p))
+        .count();
+    Ok((cnt as i32, ps.warnings))
+}
+
+/// Counts the passports in `input` that are valid according to `schema`,
+/// with the warnings for records skipped when `lenient`.
+pub fn count_valid(input: &str, schema: &str, lenient: bool) -> crate::Result<(i32, Vec<String>)> {
+    let schema = Schema::from_str(schema)?;
+    let ps = Collection::parse(input, lenient)?;
+    let cnt = ps
+        .records
+        .iter()
+        .filter(|(_, p)| schema.is_valid(p))
+        .count();
+    Ok((cnt as i32, ps.warnings))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReportFormat {
+    Text,
+    Json,
+}
 
-                match (kv[0], kv[1]) {
-                    ("byr", v) => p.byr = v.to_string(),
-                    ("iyr", v) => p.iyr = v.to_string(),
-                    ("eyr", v) => p.eyr = v.to_string(),
-                    ("hgt", v) => p.hgt = v.to_string(),
-                    ("hcl", v) => p.hcl = v.to_string(),
-                    ("ecl", v) => p.ecl = v.to_string(),
-                    ("pid", v) => p.pid = v.to_string(),
-                    ("cid", v) => p.cid = v.to_string(),
-                    _ => return Err(crate::Error::boxed(Error::InvalidInput)),
+impl FromStr for ReportFormat {
+    type Err = Box<dyn std::error::Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(ReportFormat::Text),
+            "json" => Ok(ReportFormat::Json),
+            _ => Err(crate::Error::boxed(Error::InvalidFormat)),
+        }
+    }
+}
+
+fn json_str(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Lists why each passport in `input` passes or fails `schema`, numbered
+/// from 1, followed by how many passports failed for each reason. Records
+/// skipped when `lenient` are left out, the others keep their number.
+pub fn report(
+    input: &str,
+    schema: &str,
+    format: ReportFormat,
+    lenient: bool,
+) -> crate::Result<(String, Vec<String>)> {
+    let schema = Schema::from_str(schema)?;
+    let ps = Collection::parse(input, lenient)?;
+
+    let violations: Vec<_> = ps
+        .records
+        .iter()
+        .map(|(n, p)| (*n, schema.violations(p)))
+        .collect();
+    let mut reasons: BTreeMap<String, usize> = BTreeMap::new();
+    for (_, vs) in &violations {
+        for v in vs {
+            *reasons.entry(v.reason()).or_insert(0) += 1;
+        }
+    }
+
+    let mut out = String::new();
+    match format {
+        ReportFormat::Text => {
+            for (n, vs) in &violations {
+                let vs: Vec<_> = vs
+                    .iter()
+                    .map(|v| match v {
+                        Violation::Missing(field) => format!("missing {}", field),
+                        Violation::Invalid { field, value, rule } => {
+                            format!("{} '{}' breaks {}", field, value, rule)
+                        }
+                    })
+                    .collect();
+                if vs.is_empty() {
+                    out += &format!("Passport {}: valid\n", n);
+                } else {
+                    out += &format!("Passport {}: {}\n", n, vs.join("; "));
                 }
             }
-            ps.push(p);
-            p = Passport::default();
+
+            out += &format!(
+                "Valid: {}/{}\n",
+                violations.iter().filter(|(_, vs)| vs.is_empty()).count(),
+                violations.len()
+            );
+            for (reason, cnt) in &reasons {
+                out += &format!("{}: {}\n", reason, cnt);
+            }
+        }
+        ReportFormat::Json => {
+            let passports: Vec<_> = violations
+                .iter()
+                .map(|(n, vs)| {
+                    let missing: Vec<_> = vs
+                        .iter()
+                        .filter_map(|v| match v {
+                            Violation::Missing(field) => Some(json_str(field)),
+                            _ => None,
+                        })
+                        .collect();
+                    let invalid: Vec<_> = vs
+                        .iter()
+                        .filter_map(|v| match v {
+                            Violation::Invalid { field, value, rule } => Some(format!(
+                                "{{\"field\": {}, \"value\": {}, \"rule\": {}}}",
+                                json_str(field),
+                                json_str(value),
+                                json_str(&rule.to_string())
+                            )),
+                            _ => None,
+                        })
+                        .collect();
+                    format!(
+                        "    {{\"passport\": {}, \"valid\": {}, \"missing\": [{}], \"invalid\": [{}]}}",
+                        n,
+                        vs.is_empty(),
+                        missing.join(", "),
+                        invalid.join(", ")
+                    )
+                })
+                .collect();
+            let reasons: Vec<_> = reasons
+                .iter()
+                .map(|(reason, cnt)| format!("    {}: {}", json_str(reason), cnt))
+                .collect();
+
+            out += &format!(
+                "{{\n  \"passports\": [\n{}\n  ],\n  \"failures\": {{\n{}\n  }}\n}}\n",
+                passports.join(",\n"),
+                reasons.join(",\n")
+            );
         }
-        Ok(Collection(ps))
     }
+    Ok((out, ps.warnings))
 }
 
 pub fn part1(input: &str) -> crate::Result<i32> {
-    let ps = Collection::from_str(input)?;
-    Ok(ps.0.iter().filter(|p| p.is_valid_p1()).count() as i32)
+    Ok(count_complete(input, DEFAULT_SCHEMA, false)?.0)
 }
 
 pub fn part2(input: &str) -> crate::Result<i32> {
-    let ps = Collection::from_str(input)?;
-    Ok(ps.0.iter().filter(|p| p.is_valid_p2()).count() as i32)
+    Ok(count_valid(input, DEFAULT_SCHEMA, false)?.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pattern() {
+        let p: Pattern = "#[0-9a-f]{6}".parse().unwrap();
+        assert!(p.matches("#123abc"));
+        assert!(!p.matches("#123abz"));
+        assert!(!p.matches("123abc"));
+        assert!(!p.matches("#123abc0"));
+
+        let p: Pattern = r"a+b?\d{2,}[^x-z]*\.".parse().unwrap();
+        assert!(p.matches("aa12."));
+        assert!(p.matches("ab123qq."));
+        assert!(!p.matches("b12."));
+        assert!(!p.matches("a1."));
+        assert!(!p.matches("a12y."));
+
+        for bad in &["[", "[z-a]", "a{3,1}", "a{3", "a{2,", "*", r"\"] {
+            assert!(bad.parse::<Pattern>().is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn schema() {
+        let inp = "pid:087499704 hgt:74in ecl:grn iyr:2012 eyr:2030 byr:1980
+hcl:#623a2f
+
+eyr:2029 ecl:blu cid:129 byr:1989
+iyr:2014 pid:896056539 hcl:#a97842 hgt:165cm
+
+eyr:1972 cid:100
+hcl:#18171d ecl:amb hgt:170 pid:186cm iyr:2018 byr:1926
+
+iyr:2019
+hcl:#602927 eyr:1967 hgt:170cm
+ecl:grn pid:012533040 byr:1946";
+        assert_eq!(part1(inp).unwrap(), 4);
+        assert_eq!(part2(inp).unwrap(), 2);
+
+        let schema = "hgt required units cm:150..193
+cid required";
+        assert_eq!(count_valid(inp, schema, false).unwrap().0, 1);
+
+        let signed = "tmp required int -5..5";
+        assert_eq!(count_valid("tmp:-3", signed, false).unwrap().0, 1);
+        assert_eq!(count_valid("tmp:-6", signed, false).unwrap().0, 0);
+        assert_eq!(count_valid("tmp:+3", signed, false).unwrap().0, 0);
+        assert_eq!(count_valid("tmp:-", signed, false).unwrap().0, 0);
+
+        let empty = "pid: hgt:74in ecl:grn iyr:2012 eyr:2030 byr:1980 hcl:#623a2f";
+        assert_eq!(part1(empty).unwrap(), 0);
+        assert_eq!(part1(&empty.replace("pid:", "pid:1")).unwrap(), 1);
+
+        assert!(count_valid(inp, "byr mandatory", false).is_err());
+        assert!(count_valid(inp, "byr required int 1..", false).is_err());
+        assert!(count_valid(inp, "byr required regex x", false).is_err());
+    }
+
+    #[test]
+    fn reasons() {
+        let inp = "ecl:gry hcl:#fffffd eyr:2020 byr:1937 iyr:2017 cid:147 hgt:183cm
+
+iyr:2013 ecl:amb cid:350 eyr:2023 pid:028048884
+hcl:#cfa07d byr:1929
+
+hcl:#ae17e1 iyr:2013 eyr:2024 ecl:brn pid:760753108 byr:1931 hgt:179cm";
+        let schema = "pid required pattern \\d{9}
+hgt required units cm:150..193,in:59..76
+byr required int 1930..2002";
+
+        let text = report(inp, schema, ReportFormat::Text, false).unwrap().0;
+        let lines: Vec<_> = text.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "Passport 1: missing pid",
+                "Passport 2: missing hgt; byr '1929' breaks int 1930..2002",
+                "Passport 3: valid",
+                "Valid: 1/3",
+                "byr: int 1930..2002: 1",
+                "missing hgt: 1",
+                "missing pid: 1",
+            ]
+        );
+
+        let json = report(inp, schema, ReportFormat::Json, false).unwrap().0;
+        assert!(json.contains(
+            r#"{"passport": 2, "valid": false, "missing": ["hgt"], "invalid": [{"field": "byr", "value": "1929", "rule": "int 1930..2002"}]}"#
+        ));
+        assert!(json.contains(r#"{"passport": 3, "valid": true, "missing": [], "invalid": []}"#));
+        assert!(json.contains(r#""byr: int 1930..2002": 1"#));
+        assert_eq!(json_str("a\"b\\c\n"), r#""a\"b\\c\u000a""#);
+    }
+
+    #[test]
+    fn typed() {
+        let inp = "hcl:#ae17e1 iyr:2013 eyr:2024 ecl:brn pid:760753108 byr:1931 hgt:179cm x:1
+
+byr:1920 byr:1921
+
+hgt:59 pid:1
+
+eyr:2020 hgt:60in ecl:oth";
+
+        assert!(passports(inp, false).is_err());
+        let (ps, warnings) = passports(inp, true).unwrap();
+        assert_eq!(ps.len(), 2);
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].starts_with("skipped record 2: duplicate key"));
+        assert!(warnings[1].starts_with("skipped record 3: invalid value"));
+
+        assert_eq!(ps[0].byr, Some(1931));
+        assert_eq!(ps[0].hgt, Some(Height::Cm(179)));
+        assert_eq!(ps[0].hcl, Some(Color(0xae, 0x17, 0xe1)));
+        assert_eq!(ps[0].ecl, Some(EyeColor::Brn));
+        assert_eq!(ps[0].unknown, vec![("x".to_string(), "1".to_string())]);
+        assert_eq!(
+            ps[0].to_string(),
+            "byr:1931 iyr:2013 eyr:2024 hgt:179cm hcl:#ae17e1 ecl:brn pid:760753108 x:1"
+        );
+        assert_eq!(ps[1].to_string(), "eyr:2020 hgt:60in ecl:oth");
+
+        // Only the duplicate key breaks the raw record, the short pid and
+        // unitless height are up to the schema
+        assert!(part1(inp).is_err());
+        let (cnt, warnings) = count_complete(inp, DEFAULT_SCHEMA, true).unwrap();
+        assert_eq!(cnt, 1);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            count_valid(inp, DEFAULT_SCHEMA, true).unwrap(),
+            (1, warnings)
+        );
+        let (text, _) = report(inp, DEFAULT_SCHEMA, ReportFormat::Text, true).unwrap();
+        let numbers: Vec<_> = text
+            .lines()
+            .filter_map(|l| l.strip_prefix("Passport "))
+            .map(|l| l.split(':').next().unwrap())
+            .collect();
+        assert_eq!(numbers, ["1", "3", "4"]);
+
+        assert!(count_valid("byr:1 x", DEFAULT_SCHEMA, false).is_err());
+        assert!(count_valid("byr:1 byr:2", DEFAULT_SCHEMA, false).is_err());
+        assert!(count_valid("a:b:c", DEFAULT_SCHEMA, false).is_err());
+    }
 }
@@ -1,44 +1,373 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
 #[derive(Debug)]
 enum Error {
     InvalidInput,
+    InvalidLayout,
+    InvalidLength {
+        expected: usize,
+        found: usize,
+    },
+    InvalidChar {
+        pos: usize,
+        found: char,
+        expected: [char; 2],
+    },
+    OutOfRange,
+    TooManySeats,
+    DuplicatePass {
+        id: i32,
+        lines: (usize, usize),
+    },
 }
 
-fn parse_ids(s: &str) -> crate::Result<Vec<i32>> {
-    Ok(s.lines()
-        .map(|l| {
-            i32::from_str_radix(
-                &l.replace("F", "0")
-                    .replace("B", "1")
-                    .replace("R", "1")
-                    .replace("L", "0"),
-                2,
-            )
-        })
-        .collect::<Result<Vec<_>, _>>()?)
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidInput => write!(f, "invalid input"),
+            Error::InvalidLayout => write!(f, "invalid layout, expected <rows>x<cols>"),
+            Error::InvalidLength { expected, found } => {
+                write!(f, "expected {} chars, found {}", expected, found)
+            }
+            Error::InvalidChar {
+                pos,
+                found,
+                expected,
+            } => write!(
+                f,
+                "expected '{}' or '{}' at index {}, found '{}'",
+                expected[0], expected[1], pos, found
+            ),
+            Error::OutOfRange => write!(f, "seat out of range"),
+            Error::TooManySeats => write!(f, "too many seats to number them all"),
+            Error::DuplicatePass { id, lines } => {
+                write!(f, "seat {} is on lines {} and {}", id, lines.0, lines.1)
+            }
+        }
+    }
 }
 
-pub fn part1(input: &str) -> crate::Result<i32> {
-    let id = *parse_ids(input)?
+/// The seats of a plane, `rows` from front to back with `cols` seats each.
+/// Passes narrow down the row with F/B and then the column with L/R, one
+/// binary partition per character, so a layout takes as many characters as
+/// it takes bits to number its rows and columns.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Layout {
+    rows: usize,
+    cols: usize,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Layout { rows: 128, cols: 8 }
+    }
+}
+
+impl FromStr for Layout {
+    type Err = Box<dyn std::error::Error>;
+
+    /// Parses `<rows>x<cols>`, e.g. `128x8`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (rows, cols) = s
+            .split_once('x')
+            .ok_or_else(|| crate::Error::boxed(Error::InvalidLayout))?;
+        let (rows, cols) = (rows.trim().parse()?, cols.trim().parse()?);
+        if rows == 0 || cols == 0 {
+            return Err(crate::Error::boxed(Error::InvalidLayout));
+        }
+        let layout = Layout { rows, cols };
+        layout.seats()?;
+        Ok(layout)
+    }
+}
+
+impl fmt::Display for Layout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}", self.rows, self.cols)
+    }
+}
+
+/// Number of characters needed to pick one of `n` seats.
+fn bits(n: usize) -> usize {
+    (usize::BITS - (n - 1).leading_zeros()) as usize
+}
+
+fn encode_part(out: &mut String, v: usize, n: usize, [lo, hi]: [char; 2]) {
+    for b in (0..bits(n)).rev() {
+        out.push(if v >> b & 1 == 1 { hi } else { lo });
+    }
+}
+
+fn decode_part(s: &str, offset: usize, n: usize, [lo, hi]: [char; 2]) -> crate::Result<usize> {
+    let mut v = 0;
+    for (i, c) in s.chars().enumerate() {
+        v = v << 1
+            | match c {
+                c if c == lo => 0,
+                c if c == hi => 1,
+                c => {
+                    return Err(crate::Error::boxed(Error::InvalidChar {
+                        pos: offset + i,
+                        found: c,
+                        expected: [lo, hi],
+                    }))
+                }
+            };
+    }
+    if v >= n {
+        return Err(crate::Error::boxed(Error::OutOfRange));
+    }
+    Ok(v)
+}
+
+const ROW: [char; 2] = ['F', 'B'];
+const COL: [char; 2] = ['L', 'R'];
+
+impl Layout {
+    pub fn id(&self, row: usize, col: usize) -> i32 {
+        (row * self.cols + col) as i32
+    }
+
+    /// Number of seats, as long as each of them has an `i32` ID.
+    fn seats(&self) -> crate::Result<usize> {
+        self.rows
+            .checked_mul(self.cols)
+            .filter(|&n| n - 1 <= i32::MAX as usize)
+            .ok_or_else(|| crate::Error::boxed(Error::TooManySeats) as _)
+    }
+
+    fn len(&self) -> usize {
+        bits(self.rows) + bits(self.cols)
+    }
+
+    /// The pass for the seat at `row` and `col`.
+    pub fn encode(&self, row: usize, col: usize) -> crate::Result<String> {
+        if row >= self.rows || col >= self.cols {
+            return Err(crate::Error::boxed(Error::OutOfRange));
+        }
+        let mut s = String::with_capacity(self.len());
+        encode_part(&mut s, row, self.rows, ROW);
+        encode_part(&mut s, col, self.cols, COL);
+        Ok(s)
+    }
+
+    /// The pass for the seat with the given ID.
+    pub fn encode_id(&self, id: i32) -> crate::Result<String> {
+        if id < 0 {
+            return Err(crate::Error::boxed(Error::OutOfRange));
+        }
+        let id = id as usize;
+        self.encode(id / self.cols, id % self.cols)
+    }
+
+    /// The pass for a seat given as `<id>` or `<row>,<col>`.
+    pub fn encode_seat(&self, seat: &str) -> crate::Result<String> {
+        match seat.split_once(',') {
+            Some((row, col)) => self.encode(row.trim().parse()?, col.trim().parse()?),
+            None => self.encode_id(seat.trim().parse()?),
+        }
+    }
+
+    /// The row and column of a pass. The row characters must all be F or B
+    /// and the column characters L or R, and both must be on the plane.
+    pub fn decode(&self, pass: &str) -> crate::Result<(usize, usize)> {
+        let found = pass.chars().count();
+        if found != self.len() {
+            return Err(crate::Error::boxed(Error::InvalidLength {
+                expected: self.len(),
+                found,
+            }));
+        }
+
+        let n = bits(self.rows);
+        let mid = pass.char_indices().nth(n).map_or(pass.len(), |(i, _)| i);
+        let (row, col) = pass.split_at(mid);
+        Ok((
+            decode_part(row, 0, self.rows, ROW)?,
+            decode_part(col, n, self.cols, COL)?,
+        ))
+    }
+}
+
+/// The seat IDs of all passes, erroring on two passes for the same seat.
+fn parse_ids(s: &str, layout: Layout) -> crate::Result<Vec<i32>> {
+    let mut seen = HashMap::new();
+    let mut ids = Vec::new();
+    for (n, l) in s.lines().enumerate() {
+        let (row, col) = layout.decode(l.trim())?;
+        let id = layout.id(row, col);
+        if let Some(prev) = seen.insert(id, n + 1) {
+            return Err(crate::Error::boxed(Error::DuplicatePass {
+                id,
+                lines: (prev, n + 1),
+            }));
+        }
+        ids.push(id);
+    }
+    Ok(ids)
+}
+
+/// Which seats of a plane have a pass, indexed by seat ID.
+struct SeatMap {
+    layout: Layout,
+    occupied: Vec<bool>,
+}
+
+impl SeatMap {
+    fn new(input: &str, layout: Layout) -> crate::Result<Self> {
+        let mut occupied = vec![false; layout.seats()?];
+        for id in parse_ids(input, layout)? {
+            occupied[id as usize] = true;
+        }
+        Ok(SeatMap { layout, occupied })
+    }
+
+    /// One line per row, front first, with `#` for taken and `.` for empty
+    /// seats.
+    fn render(&self) -> String {
+        let mut out = String::new();
+        for row in self.occupied.chunks(self.layout.cols) {
+            out.extend(row.iter().map(|&o| if o { '#' } else { '.' }));
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Runs of consecutive empty seat IDs, which wrap from the end of a row
+    /// to the start of the next.
+    fn empty_blocks(&self) -> Vec<RangeInclusive<i32>> {
+        let mut blocks = Vec::new();
+        let mut start = None;
+        for (id, &o) in self.occupied.iter().enumerate() {
+            match (o, start) {
+                (false, None) => start = Some(id as i32),
+                (true, Some(s)) => {
+                    blocks.push(s..=id as i32 - 1);
+                    start = None;
+                }
+                _ => (),
+            }
+        }
+        if let Some(s) = start {
+            blocks.push(s..=self.occupied.len() as i32 - 1);
+        }
+        blocks
+    }
+}
+
+/// The seat map of the plane followed by every block of empty seats.
+pub fn seat_map(input: &str, layout: Layout) -> crate::Result<String> {
+    let map = SeatMap::new(input, layout)?;
+
+    let mut out = map.render();
+    for b in map.empty_blocks() {
+        let len = b.end() - b.start() + 1;
+        if len == 1 {
+            out += &format!("Empty: {}\n", b.start());
+        } else {
+            out += &format!("Empty: {}-{} ({} seats)\n", b.start(), b.end(), len);
+        }
+    }
+    Ok(out)
+}
+
+/// The highest seat ID of all passes.
+pub fn highest_id(input: &str, layout: Layout) -> crate::Result<i32> {
+    let id = *parse_ids(input, layout)?
         .iter()
         .max()
         .ok_or_else(|| crate::Error::boxed(Error::InvalidInput))?;
     Ok(id)
 }
 
-pub fn part2(input: &str) -> crate::Result<i32> {
-    let mut ids = parse_ids(input)?;
-    ids.sort_unstable();
-
-    let id = ids
-        .windows(2)
-        .filter_map(|ns| {
-            if ns[1] - ns[0] == 2 {
-                Some(ns[0] + 1)
-            } else {
-                None
-            }
-        })
-        .next()
+/// The ID of the one seat without a pass that isn't at either end.
+pub fn own_seat(input: &str, layout: Layout) -> crate::Result<i32> {
+    let map = SeatMap::new(input, layout)?;
+
+    // Ours is the only single empty seat with taken seats on both sides, the
+    // missing seats at the very front and back form blocks at either end
+    let last = map.occupied.len() as i32 - 1;
+    let id = map
+        .empty_blocks()
+        .into_iter()
+        .find(|b| b.start() == b.end() && *b.start() > 0 && *b.end() < last)
+        .map(|b| *b.start())
         .ok_or_else(|| crate::Error::boxed(Error::InvalidInput))?;
     Ok(id)
 }
+
+pub fn part1(input: &str) -> crate::Result<i32> {
+    highest_id(input, Layout::default())
+}
+
+pub fn part2(input: &str) -> crate::Result<i32> {
+    own_seat(input, Layout::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codec() {
+        let l = Layout::default();
+        assert_eq!(l.decode("FBFBBFFRLR").unwrap(), (44, 5));
+        assert_eq!(l.id(44, 5), 357);
+        assert_eq!(l.encode(70, 7).unwrap(), "BFFFBBFRRR");
+        assert_eq!(l.encode_id(820).unwrap(), "BBFFBBFRLL");
+        for id in 0..1024 {
+            let (row, col) = l.decode(&l.encode_id(id).unwrap()).unwrap();
+            assert_eq!(l.id(row, col), id);
+        }
+
+        assert!(l.decode("FBFBBFFRL").is_err());
+        assert!(l.decode("FBFBBFFRLB").is_err());
+        assert!(l.decode("FBFRBFFRLR").is_err());
+        assert!(l.decode("FBFBBFFRLÉ").is_err());
+        assert!(l.encode(128, 0).is_err());
+        assert!(l.encode_id(1024).is_err());
+        assert_eq!(l.encode_seat("44, 5").unwrap(), "FBFBBFFRLR");
+        assert_eq!(l.encode_seat("357").unwrap(), "FBFBBFFRLR");
+
+        // 3 rows need 2 characters, the fourth combination is off the plane
+        let small: Layout = "3x5".parse().unwrap();
+        assert_eq!(small.encode(2, 4).unwrap(), "BFRLL");
+        assert_eq!(small.decode("BFRLL").unwrap(), (2, 4));
+        assert!(small.decode("BBLLL").is_err());
+        assert!(small.decode("FFRRR").is_err());
+        assert_eq!(Layout::from_str("1x1").unwrap().encode(0, 0).unwrap(), "");
+        assert!(Layout::from_str("0x8").is_err());
+        assert_eq!(small.to_string(), "3x5");
+        assert!(Layout::from_str("65536x32768").is_ok());
+        assert!(Layout::from_str("65536x32769").is_err());
+        assert!(Layout::from_str(&format!("{}x2", usize::MAX)).is_err());
+    }
+
+    #[test]
+    fn seats() {
+        let l: Layout = "3x4".parse().unwrap();
+        let passes: Vec<_> = [1, 2, 4, 6, 7, 8]
+            .iter()
+            .map(|&id| l.encode_id(id).unwrap())
+            .collect();
+        let map = SeatMap::new(&passes.join("\n"), l).unwrap();
+        assert_eq!(map.render(), ".##.\n#.##\n#...\n");
+        assert_eq!(map.empty_blocks(), vec![0..=0, 3..=3, 5..=5, 9..=11]);
+        assert_eq!(
+            seat_map(&passes.join("\n"), l).unwrap().lines().last(),
+            Some("Empty: 9-11 (3 seats)")
+        );
+        assert_eq!(highest_id(&passes.join("\n"), l).unwrap(), 8);
+        let passes: Vec<_> = [1, 2, 3, 4, 6, 7]
+            .iter()
+            .map(|&id| l.encode_id(id).unwrap())
+            .collect();
+        assert_eq!(own_seat(&passes.join("\n"), l).unwrap(), 5);
+        assert!(part2(&passes.join("\n")).is_err());
+
+        assert!(parse_ids("FBFBBFFRLR\nBFFFBBFRRR\nFBFBBFFRLR", Layout::default()).is_err());
+    }
+}
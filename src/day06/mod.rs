@@ -1,32 +1,163 @@
-use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
 
-pub fn part1(input: &str) -> crate::Result<i32> {
-    let sum = input
+#[derive(Debug)]
+enum Error {
+    InvalidInput,
+    InvalidQuorum,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidInput => write!(f, "invalid input"),
+            Error::InvalidQuorum => write!(
+                f,
+                "invalid quorum, expected any, all, at-least=<k> or exactly=<k>"
+            ),
+        }
+    }
+}
+
+/// How many people of a group must have answered yes to a question for it
+/// to count.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Quorum {
+    Any,
+    All,
+    AtLeast(usize),
+    Exactly(usize),
+}
+
+impl FromStr for Quorum {
+    type Err = Box<dyn std::error::Error>;
+
+    /// Parses `any`, `all`, `at-least=<k>` or `exactly=<k>`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('=') {
+            None if s == "any" => Ok(Quorum::Any),
+            None if s == "all" => Ok(Quorum::All),
+            Some(("at-least", k)) => Ok(Quorum::AtLeast(k.parse()?)),
+            Some(("exactly", k)) => Ok(Quorum::Exactly(k.parse()?)),
+            _ => Err(crate::Error::boxed(Error::InvalidQuorum)),
+        }
+    }
+}
+
+/// The answers of each person as a mask with bit 0 for `a` up to bit 25 for
+/// `z`, grouped.
+fn parse(input: &str) -> crate::Result<Vec<Vec<u32>>> {
+    input
         .split("\n\n")
-        .map(|e| {
-            e.chars()
-                .filter(|c| !c.is_whitespace())
-                .collect::<HashSet<_>>()
-                .len() as i32
+        .map(|g| {
+            g.lines()
+                .map(|l| {
+                    l.trim().bytes().try_fold(0u32, |m, b| match b {
+                        b'a'..=b'z' => Ok(m | 1 << (b - b'a')),
+                        _ => Err(crate::Error::boxed(Error::InvalidInput).into()),
+                    })
+                })
+                .collect()
         })
-        .sum();
-    Ok(sum)
+        .collect()
+}
+
+/// How many people of `group` answered yes to each question.
+fn counts(group: &[u32]) -> [usize; 26] {
+    let mut cnts = [0; 26];
+    for &m in group {
+        for (q, cnt) in cnts.iter_mut().enumerate() {
+            *cnt += (m >> q & 1) as usize;
+        }
+    }
+    cnts
+}
+
+fn quorum_mask(group: &[u32], quorum: Quorum) -> u32 {
+    match quorum {
+        Quorum::Any => group.iter().fold(0, |acc, m| acc | m),
+        Quorum::All => group.iter().fold((1 << 26) - 1, |acc, m| acc & m),
+        Quorum::AtLeast(k) => counts(group)
+            .iter()
+            .enumerate()
+            .filter(|&(_, &n)| n >= k)
+            .fold(0, |acc, (q, _)| acc | 1 << q),
+        Quorum::Exactly(k) => counts(group)
+            .iter()
+            .enumerate()
+            .filter(|&(_, &n)| n == k)
+            .fold(0, |acc, (q, _)| acc | 1 << q),
+    }
+}
+
+/// Sums over all groups the number of questions meeting `quorum`.
+pub fn count_quorum(input: &str, quorum: Quorum) -> crate::Result<u32> {
+    Ok(parse(input)?
+        .iter()
+        .map(|g| quorum_mask(g, quorum).count_ones())
+        .sum())
+}
+
+/// For every question, how many people answered yes across all groups.
+pub fn histogram(input: &str) -> crate::Result<String> {
+    let mut cnts = [0; 26];
+    for g in parse(input)? {
+        for (total, n) in cnts.iter_mut().zip(counts(&g).iter()) {
+            *total += n;
+        }
+    }
+
+    let mut out = String::new();
+    for (q, n) in cnts.iter().enumerate() {
+        out += &format!("{}: {}\n", (b'a' + q as u8) as char, n);
+    }
+    Ok(out)
+}
+
+pub fn part1(input: &str) -> crate::Result<i32> {
+    Ok(count_quorum(input, Quorum::Any)? as i32)
 }
 
 pub fn part2(input: &str) -> crate::Result<i32> {
-    let sum = input
-        .split("\n\n")
-        .map(|e| {
-            let mut ns = HashMap::new();
-            for c in e.chars() {
-                *ns.entry(c).or_insert(0) += 1;
-            }
-
-            let grp_sz = *ns.get(&'\n').unwrap_or(&0) + 1;
-            ns.into_iter()
-                .filter(|(c, n)| *c != '\n' && *n == grp_sz)
-                .count() as i32
-        })
-        .sum();
-    Ok(sum)
+    Ok(count_quorum(input, Quorum::All)? as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EX: &str = "abc
+
+a
+b
+c
+
+ab
+ac
+
+a
+a
+a
+a
+
+b";
+
+    #[test]
+    fn quorum() {
+        assert_eq!(part1(EX).unwrap(), 11);
+        assert_eq!(part2(EX).unwrap(), 6);
+        assert_eq!(count_quorum(EX, Quorum::AtLeast(1)).unwrap(), 11);
+        assert_eq!(count_quorum(EX, Quorum::AtLeast(2)).unwrap(), 2);
+        assert_eq!(count_quorum(EX, Quorum::Exactly(1)).unwrap(), 9);
+        assert_eq!(count_quorum(EX, "exactly=4".parse().unwrap()).unwrap(), 1);
+        assert!(Quorum::from_str("most").is_err());
+        assert!(part1("ab\nA").is_err());
+
+        let hist = histogram(EX).unwrap();
+        assert_eq!(
+            &hist.lines().collect::<Vec<_>>()[..3],
+            ["a: 8", "b: 4", "c: 3"]
+        );
+        assert_eq!(hist.lines().count(), 26);
+    }
 }
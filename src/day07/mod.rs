@@ -1,13 +1,28 @@
 use std::collections::{HashMap, HashSet};
+use std::fmt;
 
 #[derive(Debug)]
 enum Error {
     InvalidInput,
+    UnknownColor(String),
+    Cycle(String),
+    Overflow,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidInput => write!(f, "invalid input"),
+            Error::UnknownColor(color) => write!(f, "unknown color '{}'", color),
+            Error::Cycle(color) => write!(f, "bags containing '{}' contain themselves", color),
+            Error::Overflow => write!(f, "overflow"),
+        }
+    }
 }
 
 type RuleSet<'a> = HashMap<&'a str, Vec<(i32, &'a str)>>;
 
-fn parse_rules(s: &str) -> crate::Result<RuleSet> {
+fn parse_rules(s: &str) -> crate::Result<RuleSet<'_>> {
     let rules = s
         .lines()
         .map(|l| -> crate::Result<_> {
@@ -42,34 +57,417 @@ fn parse_rules(s: &str) -> crate::Result<RuleSet> {
     Ok(rules)
 }
 
-fn find_outer_layers<'a>(clr: &str, rules: &'a RuleSet, layers: &mut HashSet<&'a str>) {
-    for (k, v) in rules.iter() {
-        if v.iter().any(|(_, c)| *c == clr) {
-            layers.insert(k);
-            find_outer_layers(*k, rules, layers);
+/// The rules indexed both ways, from each color to the bags it directly
+/// contains and to the bags that directly contain it.
+struct Graph<'a> {
+    rules: RuleSet<'a>,
+    parents: HashMap<&'a str, Vec<&'a str>>,
+}
+
+impl<'a> Graph<'a> {
+    fn new(s: &'a str) -> crate::Result<Self> {
+        let rules = parse_rules(s)?;
+        let mut parents: HashMap<_, Vec<_>> = HashMap::new();
+        for (&k, v) in &rules {
+            for &(_, c) in v {
+                parents.entry(c).or_default().push(k);
+            }
+        }
+        Ok(Graph { rules, parents })
+    }
+
+    fn children(&self, clr: &str) -> crate::Result<&[(i32, &'a str)]> {
+        match self.rules.get(clr) {
+            Some(cs) => Ok(cs),
+            None => Err(crate::Error::boxed(Error::UnknownColor(clr.to_string()))),
+        }
+    }
+
+    /// Every color that eventually contains `clr`, visiting each rule once.
+    fn outer_layers(&self, clr: &str) -> crate::Result<HashSet<&'a str>> {
+        self.children(clr)?;
+
+        let mut layers = HashSet::new();
+        let mut todo = vec![clr];
+        while let Some(c) = todo.pop() {
+            for &p in self.parents.get(c).into_iter().flatten() {
+                if layers.insert(p) {
+                    todo.push(p);
+                }
+            }
+        }
+        Ok(layers)
+    }
+
+    /// Every color that `clr` eventually contains.
+    fn inner_layers(&self, clr: &str) -> crate::Result<HashSet<&'a str>> {
+        let mut layers = HashSet::new();
+        let mut todo = vec![clr];
+        while let Some(c) = todo.pop() {
+            for &(_, cc) in self.children(c)? {
+                if layers.insert(cc) {
+                    todo.push(cc);
+                }
+            }
+        }
+        Ok(layers)
+    }
+
+    /// Every color inside any of `starts`, and the starts themselves, with
+    /// each color after all the colors it holds. The search keeps its own
+    /// stack, so deep nesting can't overflow, and finding a color inside
+    /// itself is an error.
+    fn post_order(&self, starts: &[&'a str]) -> crate::Result<Vec<&'a str>> {
+        let mut order = Vec::new();
+        let mut done = HashSet::new();
+        let mut open = HashSet::new();
+        let mut stack: Vec<_> = starts.iter().rev().map(|&c| (c, false)).collect();
+        while let Some((c, expanded)) = stack.pop() {
+            if done.contains(c) {
+                continue;
+            }
+
+            if expanded {
+                open.remove(c);
+                done.insert(c);
+                order.push(c);
+                continue;
+            }
+
+            // Only the colors on the path down to `c` are still open
+            if !open.insert(c) {
+                return Err(crate::Error::boxed(Error::Cycle(c.to_string())));
+            }
+            stack.push((c, true));
+            stack.extend(self.children(c)?.iter().map(|&(_, cc)| (cc, false)));
+        }
+        Ok(order)
+    }
+
+    /// How many bags each color inside `clr`, and `clr` itself, holds in
+    /// total, counting every color once no matter how many paths lead to it.
+    fn totals(&self, clr: &'a str) -> crate::Result<HashMap<&'a str, i64>> {
+        let mut totals: HashMap<&str, i64> = HashMap::new();
+        for c in self.post_order(&[clr])? {
+            let cnt = self.children(c)?.iter().try_fold(0i64, |acc, &(n, cc)| {
+                (totals[cc] + 1)
+                    .checked_mul(n as i64)
+                    .and_then(|m| acc.checked_add(m))
+            });
+            let cnt = cnt.ok_or_else(|| crate::Error::boxed(Error::Overflow))?;
+            totals.insert(c, cnt);
         }
+        Ok(totals)
+    }
+
+    fn count_contained_bags(&self, clr: &'a str) -> crate::Result<i64> {
+        Ok(self.totals(clr)?[clr])
     }
+
+    /// What each color `clr` directly holds adds to `count_contained_bags`:
+    /// how many of it, and how many bags those hold including themselves.
+    fn breakdown(&self, clr: &'a str) -> crate::Result<Vec<(i32, &'a str, i64)>> {
+        let totals = self.totals(clr)?;
+        Ok(self
+            .children(clr)?
+            .iter()
+            .map(|&(n, cc)| (n, cc, n as i64 * (totals[cc] + 1)))
+            .collect())
+    }
+
+    /// The longest chain of bags inside bags, starting from `from` or from
+    /// any color. Ties go to the alphabetically first chain.
+    fn deepest_chain(&self, from: Option<&'a str>) -> crate::Result<Vec<&'a str>> {
+        let starts = match from {
+            Some(clr) => vec![clr],
+            None => {
+                let mut all: Vec<_> = self.rules.keys().copied().collect();
+                all.sort_unstable();
+                all
+            }
+        };
+
+        // Depth of the deepest chain below each color, and where it goes next
+        let mut depth: HashMap<&str, (usize, Option<&str>)> = HashMap::new();
+        for c in self.post_order(&starts)? {
+            let mut best = (0, None);
+            for &(_, cc) in self.children(c)? {
+                let d = depth[cc].0 + 1;
+                if d > best.0 || (d == best.0 && best.1.map(|b| cc < b).unwrap_or(true)) {
+                    best = (d, Some(cc));
+                }
+            }
+            depth.insert(c, best);
+        }
+
+        let mut c = *starts
+            .iter()
+            .rev()
+            .max_by_key(|c| depth[*c].0)
+            .ok_or_else(|| crate::Error::boxed(Error::InvalidInput))?;
+        let mut chain = vec![c];
+        while let (_, Some(next)) = depth[c] {
+            chain.push(next);
+            c = next;
+        }
+        Ok(chain)
+    }
+
+    /// Every way `inner` ends up inside `outer`, as the colors from `outer`
+    /// down to `inner` with how many `inner` bags that path accounts for.
+    /// Only colors that can hold `inner` are explored.
+    fn paths(&self, outer: &'a str, inner: &'a str) -> crate::Result<Vec<(Vec<&'a str>, i64)>> {
+        self.children(outer)?;
+        let relevant = self.outer_layers(inner)?;
+        if !relevant.contains(outer) {
+            return Ok(Vec::new());
+        }
+
+        let mut paths = Vec::new();
+        // The current path with the multiplicity so far and the index of the
+        // next child to try at each step
+        let mut path = vec![(outer, 1i64, 0)];
+        while let Some(&mut (c, mult, ref mut next)) = path.last_mut() {
+            let children = self.children(c)?;
+            let (n, cc) = match children.get(*next) {
+                Some(&child) => child,
+                None => {
+                    path.pop();
+                    continue;
+                }
+            };
+            *next += 1;
+
+            let mult = mult
+                .checked_mul(n as i64)
+                .ok_or_else(|| crate::Error::boxed(Error::Overflow))?;
+            if cc == inner {
+                let mut colors: Vec<_> = path.iter().map(|&(c, _, _)| c).collect();
+                colors.push(cc);
+                paths.push((colors, mult));
+            } else if relevant.contains(cc) {
+                if path.iter().any(|&(c, _, _)| c == cc) {
+                    return Err(crate::Error::boxed(Error::Cycle(cc.to_string())));
+                }
+                path.push((cc, mult, 0));
+            }
+        }
+        Ok(paths)
+    }
+}
+
+fn dot_id(clr: &str) -> String {
+    format!("\"{}\"", clr.replace('\\', "\\\\").replace('"', "\\\""))
 }
 
-fn count_contained_bags(clr: &str, rules: &RuleSet) -> crate::Result<i32> {
-    let mut cnt = 0;
-    for (ccnt, cclr) in rules
-        .get(&clr)
-        .ok_or_else(|| crate::Error::boxed(Error::InvalidInput))?
-    {
-        cnt += ccnt + ccnt * count_contained_bags(*cclr, rules)?;
+/// The rules as a Graphviz digraph with an edge from each bag to each bag it
+/// directly holds, labelled with how many. With `from` only the colors inside
+/// that one are kept, with `to` only those that can hold it, both colors
+/// included.
+pub fn dot(input: &str, from: Option<&str>, to: Option<&str>) -> crate::Result<String> {
+    let g = Graph::new(input)?;
+
+    let mut nodes: HashSet<&str> = g.rules.keys().copied().collect();
+    if let Some(from) = from {
+        let mut inner = g.inner_layers(from)?;
+        inner.insert(from);
+        nodes.retain(|c| inner.contains(c));
+    }
+    if let Some(to) = to {
+        let mut outer = g.outer_layers(to)?;
+        outer.insert(to);
+        nodes.retain(|c| outer.contains(c));
+    }
+    let mut nodes: Vec<_> = nodes.into_iter().collect();
+    nodes.sort_unstable();
+
+    let mut out = String::from("digraph bags {\n");
+    for &c in &nodes {
+        out += &format!("    {};\n", dot_id(c));
+    }
+    for &c in &nodes {
+        for &(n, cc) in g.children(c)? {
+            if nodes.binary_search(&cc).is_ok() {
+                out += &format!("    {} -> {} [label=\"{}\"];\n", dot_id(c), dot_id(cc), n);
+            }
+        }
+    }
+    out += "}\n";
+    Ok(out)
+}
+
+/// Lists every path by which `inner` bags end up inside an `outer` bag,
+/// with how many `inner` bags each accounts for, followed by the total.
+pub fn paths(input: &str, outer: &str, inner: &str) -> crate::Result<String> {
+    let g = Graph::new(input)?;
+
+    let mut out = String::new();
+    let mut total = 0i64;
+    for (colors, n) in g.paths(outer, inner)? {
+        out += &format!("{}: {}\n", colors.join(" > "), n);
+        total = total
+            .checked_add(n)
+            .ok_or_else(|| crate::Error::boxed(Error::Overflow))?;
+    }
+    out += &format!("Total {} in {}: {}\n", inner, outer, total);
+    Ok(out)
+}
+
+/// The longest chain of bags inside bags, from `from` or from any color.
+pub fn deepest(input: &str, from: Option<&str>) -> crate::Result<String> {
+    let g = Graph::new(input)?;
+    let chain = g.deepest_chain(from)?;
+    Ok(format!("{} ({} deep)", chain.join(" > "), chain.len() - 1))
+}
+
+/// How many bags each color directly inside `clr` adds to its total.
+pub fn breakdown(input: &str, clr: &str) -> crate::Result<String> {
+    let g = Graph::new(input)?;
+
+    let mut out = String::new();
+    for (n, cc, total) in g.breakdown(clr)? {
+        out += &format!("{} {}: {}\n", n, cc, total);
     }
-    Ok(cnt)
+    out += &format!("Total in {}: {}\n", clr, g.count_contained_bags(clr)?);
+    Ok(out)
+}
+
+/// The number of colors that can eventually hold a `clr` bag.
+pub fn containers(input: &str, clr: &str) -> crate::Result<usize> {
+    Ok(Graph::new(input)?.outer_layers(clr)?.len())
+}
+
+/// The number of bags inside a `clr` bag.
+pub fn contained(input: &str, clr: &str) -> crate::Result<i64> {
+    Graph::new(input)?.count_contained_bags(clr)
 }
 
 pub fn part1(input: &str) -> crate::Result<i32> {
-    let rules = parse_rules(input)?;
-    let mut layers = HashSet::new();
-    find_outer_layers("shiny gold", &rules, &mut layers);
-    Ok(layers.len() as i32)
+    Ok(containers(input, "shiny gold")? as i32)
+}
+
+pub fn part2(input: &str) -> crate::Result<i64> {
+    contained(input, "shiny gold")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EX: &str = "light red bags contain 1 bright white bag, 2 muted yellow bags.
+dark orange bags contain 3 bright white bags, 4 muted yellow bags.
+bright white bags contain 1 shiny gold bag.
+muted yellow bags contain 2 shiny gold bags, 9 faded blue bags.
+shiny gold bags contain 1 dark olive bag, 2 vibrant plum bags.
+dark olive bags contain 3 faded blue bags, 4 dotted black bags.
+vibrant plum bags contain 5 faded blue bags, 6 dotted black bags.
+faded blue bags contain no other bags.
+dotted black bags contain no other bags.";
+
+    #[test]
+    fn graph() {
+        assert_eq!(part1(EX).unwrap(), 4);
+        assert_eq!(part2(EX).unwrap(), 32);
+        assert_eq!(containers(EX, "muted yellow").unwrap(), 2);
+        assert_eq!(contained(EX, "light red").unwrap(), 186);
+        assert!(contained(EX, "plaid magenta").is_err());
+
+        let cycle = "a b bags contain 1 c d bag.
+c d bags contain 2 a b bags.";
+        assert!(contained(cycle, "a b").is_err());
+        assert_eq!(containers(cycle, "a b").unwrap(), 2);
+
+        // Deep enough that recursing per level would overflow the stack
+        let name = |i: usize| -> String {
+            let letters: String = (0..4)
+                .map(|d| (b'a' + (i / 26usize.pow(d) % 26) as u8) as char)
+                .collect();
+            format!("{} x", letters)
+        };
+        let deep: String = (0..100_000)
+            .map(|i| format!("{} bags contain 1 {} bag.\n", name(i), name(i + 1)))
+            .chain(std::iter::once(format!(
+                "{} bags contain no other bags.",
+                name(100_000)
+            )))
+            .collect();
+        assert_eq!(contained(&deep, &name(0)).unwrap(), 100_000);
+        assert_eq!(containers(&deep, &name(100_000)).unwrap(), 100_000);
+    }
+
+    #[test]
+    fn graphviz() {
+        let all = dot(EX, None, None).unwrap();
+        assert!(all.starts_with("digraph bags {\n"));
+        assert_eq!(all.matches(" -> ").count(), 13);
+        assert!(all.contains("    \"light red\" -> \"muted yellow\" [label=\"2\"];\n"));
+
+        let from = dot(EX, Some("shiny gold"), None).unwrap();
+        assert_eq!(from.matches(";\n").count(), 5 + 6);
+        assert!(!from.contains("light red"));
+
+        let between = dot(EX, Some("light red"), Some("shiny gold")).unwrap();
+        assert_eq!(
+            between,
+            "digraph bags {
+    \"bright white\";
+    \"light red\";
+    \"muted yellow\";
+    \"shiny gold\";
+    \"bright white\" -> \"shiny gold\" [label=\"1\"];
+    \"light red\" -> \"bright white\" [label=\"1\"];
+    \"light red\" -> \"muted yellow\" [label=\"2\"];
+    \"muted yellow\" -> \"shiny gold\" [label=\"2\"];
 }
+"
+        );
+        assert!(dot(EX, Some("plaid magenta"), None).is_err());
+        assert_eq!(dot_id("a\"b"), "\"a\\\"b\"");
+    }
 
-pub fn part2(input: &str) -> crate::Result<i32> {
-    let rules = parse_rules(input)?;
-    Ok(count_contained_bags("shiny gold", &rules)?)
+    #[test]
+    fn queries() {
+        let g = Graph::new(EX).unwrap();
+
+        let mut ps = g.paths("light red", "shiny gold").unwrap();
+        ps.sort();
+        assert_eq!(
+            ps,
+            vec![
+                (vec!["light red", "bright white", "shiny gold"], 1),
+                (vec!["light red", "muted yellow", "shiny gold"], 4),
+            ]
+        );
+        let ps = g.paths("light red", "faded blue").unwrap();
+        assert_eq!(ps.len(), 5);
+        assert_eq!(ps.iter().map(|p| p.1).sum::<i64>(), 3 + 10 + 18 + 12 + 40);
+        assert!(g.paths("shiny gold", "light red").unwrap().is_empty());
+
+        assert_eq!(
+            g.deepest_chain(None).unwrap(),
+            [
+                "dark orange",
+                "bright white",
+                "shiny gold",
+                "dark olive",
+                "dotted black"
+            ]
+        );
+        assert_eq!(g.deepest_chain(Some("faded blue")).unwrap(), ["faded blue"]);
+
+        assert_eq!(
+            g.breakdown("shiny gold").unwrap(),
+            [(1, "dark olive", 8), (2, "vibrant plum", 24)]
+        );
+        assert!(breakdown(EX, "shiny gold")
+            .unwrap()
+            .ends_with("Total in shiny gold: 32\n"));
+
+        let cycle = "a b bags contain 1 c d bag.
+c d bags contain 2 a b bags, 1 e f bag.
+e f bags contain no other bags.";
+        let g = Graph::new(cycle).unwrap();
+        assert!(g.paths("a b", "e f").is_err());
+        assert!(g.deepest_chain(None).is_err());
+    }
 }
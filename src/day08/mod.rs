@@ -1,89 +1,1367 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::io;
+use std::str::FromStr;
 
 #[derive(Debug)]
 enum Error {
     InvalidInput,
+    Syntax(usize),
+    UnknownLabel(usize, String),
+    DuplicateLabel(usize, String),
+    UnknownOpcode(usize, String),
+    WrongArity(usize, String),
+    InvalidOpcode(String),
+    InvalidBreakpoint,
+    InvalidExpression,
 }
 
-#[derive(Debug)]
-enum Res {
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidInput => write!(f, "invalid input"),
+            Error::Syntax(n) => write!(f, "syntax error on line {}", n),
+            Error::UnknownLabel(n, label) => write!(f, "unknown label '{}' on line {}", label, n),
+            Error::DuplicateLabel(n, label) => {
+                write!(f, "duplicate label '{}' on line {}", label, n)
+            }
+            Error::UnknownOpcode(n, op) => write!(f, "unknown opcode '{}' on line {}", op, n),
+            Error::WrongArity(n, op) => {
+                write!(f, "wrong number of operands for '{}' on line {}", op, n)
+            }
+            Error::InvalidOpcode(name) => write!(f, "invalid opcode name '{}'", name),
+            Error::InvalidBreakpoint => write!(f, "invalid breakpoint, expected ip=<n> or acc=<n>"),
+            Error::InvalidExpression => write!(f, "invalid expression"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Instruction {
+    Acc(i32),
+    Jmp(i32),
+    Nop(i32),
+    /// An opcode registered with an `InstructionSet`, by its index there.
+    Ext {
+        op: usize,
+        name: &'static str,
+        args: [i32; MAX_ARITY],
+        arity: usize,
+    },
+}
+
+impl Instruction {
+    /// The instruction with jmp and nop swapped, if it is one of them.
+    fn flipped(self) -> Option<Self> {
+        match self {
+            Instruction::Jmp(n) => Some(Instruction::Nop(n)),
+            Instruction::Nop(n) => Some(Instruction::Jmp(n)),
+            Instruction::Acc(_) | Instruction::Ext { .. } => None,
+        }
+    }
+
+    /// Where execution continues after this instruction at `addr`, for the
+    /// base instructions. Extension opcodes move `ip` themselves and may jump
+    /// anywhere, so they are taken to fall through here.
+    fn next(self, addr: usize) -> i32 {
+        match self {
+            Instruction::Jmp(n) => addr as i32 + n,
+            _ => addr as i32 + 1,
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Instruction::Acc(n) => write!(f, "acc {:+}", n),
+            Instruction::Jmp(n) => write!(f, "jmp {:+}", n),
+            Instruction::Nop(n) => write!(f, "nop {:+}", n),
+            Instruction::Ext {
+                name, args, arity, ..
+            } => {
+                write!(f, "{}", name)?;
+                for arg in &args[..*arity] {
+                    write!(f, " {:+}", arg)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operand<'s> {
+    Num(i32),
+    Label(&'s str),
+}
+
+impl fmt::Display for Operand<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Operand::Num(n) => write!(f, "{:+}", n),
+            Operand::Label(l) => write!(f, "{}", l),
+        }
+    }
+}
+
+/// One line of assembler source: `[<label>:] [<op> <operands>] [# <comment>]`.
+/// Operands are signed offsets or the name of a label, which stands for the
+/// offset from this instruction to the label.
+#[derive(Debug, Default)]
+struct Line<'s> {
+    label: Option<&'s str>,
+    insn: Option<(&'s str, Vec<Operand<'s>>)>,
+    comment: Option<&'s str>,
+}
+
+fn is_label(s: &str) -> bool {
+    let mut cs = s.chars();
+    cs.next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && cs.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+impl<'s> Line<'s> {
+    fn parse(l: &'s str) -> Option<Self> {
+        let mut line = Line::default();
+
+        let code = match l.split_once('#') {
+            Some((code, comment)) => {
+                line.comment = Some(comment.trim());
+                code
+            }
+            None => l,
+        };
+        let code = match code.split_once(':') {
+            Some((label, rest)) if is_label(label.trim()) => {
+                line.label = Some(label.trim());
+                rest
+            }
+            Some(_) => return None,
+            None => code,
+        };
+
+        let mut ts = code.split_whitespace();
+        if let Some(op) = ts.next() {
+            let args = ts
+                .map(|arg| match arg.parse() {
+                    Ok(n) => Some(Operand::Num(n)),
+                    Err(_) if is_label(arg) => Some(Operand::Label(arg)),
+                    Err(_) => None,
+                })
+                .collect::<Option<_>>()?;
+            line.insn = Some((op, args));
+        }
+        Some(line)
+    }
+}
+
+impl fmt::Display for Line<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut out = String::new();
+        if let Some(label) = self.label {
+            out += &format!("{}:", label);
+        }
+        if let Some((op, args)) = &self.insn {
+            if self.label.is_some() {
+                out += "\n";
+            }
+            out += &format!("    {}", op);
+            for arg in args {
+                out += &format!(" {}", arg);
+            }
+        }
+        if let Some(comment) = self.comment {
+            if !out.is_empty() && !out.ends_with('\n') {
+                out += "  ";
+            }
+            out += &format!("# {}", comment);
+        }
+        write!(f, "{}", out.trim_end())
+    }
+}
+
+fn parse_lines(s: &str) -> crate::Result<Vec<Line<'_>>> {
+    s.lines()
+        .enumerate()
+        .map(|(n, l)| {
+            Line::parse(l).ok_or_else(|| crate::Error::boxed(Error::Syntax(n + 1)).into())
+        })
+        .collect()
+}
+
+/// The registers of the VM, plus how many instructions it has executed.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct State {
+    pub ip: i32,
+    pub acc: i32,
+    pub steps: usize,
+}
+
+const MAX_ARITY: usize = 3;
+
+/// What an extension opcode does to the state given its operands, possibly
+/// writing to the output. It has to move `ip` on itself, like `acc` and
+/// `nop` do by adding one, and returns `None` if the state would overflow.
+pub type Semantics = fn(&mut State, &[i32], &mut Vec<i32>) -> Option<()>;
+
+struct Opcode {
+    name: &'static str,
+    arity: usize,
+    exec: Semantics,
+}
+
+/// The opcodes a program may use: `acc`, `jmp` and `nop` with one operand
+/// each, plus whatever is registered.
+#[derive(Default)]
+pub struct InstructionSet {
+    ops: Vec<Opcode>,
+}
+
+static BASE: InstructionSet = InstructionSet { ops: Vec::new() };
+
+impl InstructionSet {
+    /// Adds the opcode `name` taking `arity` operands. Names must look like
+    /// labels and can't be taken already.
+    pub fn register(
+        &mut self,
+        name: &'static str,
+        arity: usize,
+        exec: Semantics,
+    ) -> crate::Result<()> {
+        let taken =
+            ["acc", "jmp", "nop"].contains(&name) || self.ops.iter().any(|o| o.name == name);
+        if taken || !is_label(name) || arity > MAX_ARITY {
+            return Err(crate::Error::boxed(Error::InvalidOpcode(name.to_string())));
+        }
+        self.ops.push(Opcode { name, arity, exec });
+        Ok(())
+    }
+
+    /// `mul <n>` multiplies the accumulator, `jz <offset>` jumps if it is
+    /// zero and `out` writes it to the output.
+    pub fn extended() -> Self {
+        let mut isa = InstructionSet::default();
+        let exts: [(&'static str, usize, Semantics); 3] = [
+            ("mul", 1, |s, args, _| {
+                s.acc = s.acc.checked_mul(args[0])?;
+                s.ip += 1;
+                Some(())
+            }),
+            ("jz", 1, |s, args, _| {
+                s.ip = s.ip.checked_add(if s.acc == 0 { args[0] } else { 1 })?;
+                Some(())
+            }),
+            ("out", 0, |s, _, out| {
+                out.push(s.acc);
+                s.ip += 1;
+                Some(())
+            }),
+        ];
+        for (name, arity, exec) in exts {
+            isa.register(name, arity, exec).unwrap();
+        }
+        isa
+    }
+
+    /// The instruction for `op` with `args`, on line `n` of the source.
+    fn instruction(&self, n: usize, op: &str, args: &[i32]) -> crate::Result<Instruction> {
+        let ext = self.ops.iter().position(|o| o.name == op);
+        let arity = match (op, ext) {
+            ("acc" | "jmp" | "nop", _) => 1,
+            (_, Some(i)) => self.ops[i].arity,
+            (_, None) => return Err(crate::Error::boxed(Error::UnknownOpcode(n, op.to_string()))),
+        };
+        if args.len() != arity {
+            return Err(crate::Error::boxed(Error::WrongArity(n, op.to_string())));
+        }
+
+        Ok(match (op, ext) {
+            ("acc", _) => Instruction::Acc(args[0]),
+            ("jmp", _) => Instruction::Jmp(args[0]),
+            ("nop", _) => Instruction::Nop(args[0]),
+            (_, Some(i)) => {
+                let mut a = [0; MAX_ARITY];
+                a[..arity].copy_from_slice(args);
+                Instruction::Ext {
+                    op: i,
+                    name: self.ops[i].name,
+                    args: a,
+                    arity,
+                }
+            }
+            (_, None) => unreachable!(),
+        })
+    }
+}
+
+/// Why a run stopped, with the accumulator at that point.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Res {
+    /// About to execute an instruction for the second time.
     Loop(i32),
+    /// Jumped to just past the last instruction.
     Halt(i32),
+    /// Jumped anywhere else outside the program.
+    OutOfBounds { ip: i32, acc: i32 },
+    /// Executed the maximum number of steps.
+    StepLimit(i32),
+    /// The instruction at `ip` would overflow the accumulator or `ip`.
+    Overflow { ip: i32, acc: i32 },
 }
 
-fn run(program: &[(&str, i32)]) -> crate::Result<Res> {
-    let mut acc = 0;
-    let mut ip = 0;
-    let mut visited = HashSet::new();
-    loop {
-        if ip == program.len() as i32 {
-            return Ok(Res::Halt(acc));
-        } else if !visited.insert(ip) {
-            return Ok(Res::Loop(acc));
+/// When a run stops besides leaving the program.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Termination {
+    pub detect_loops: bool,
+    pub max_steps: Option<usize>,
+}
+
+impl Default for Termination {
+    fn default() -> Self {
+        Termination {
+            detect_loops: true,
+            max_steps: None,
+        }
+    }
+}
+
+impl Termination {
+    /// How many steps a run without loop detection and without a limit of
+    /// its own may take, so that every run stops.
+    pub const DEFAULT_MAX_STEPS: usize = 10_000_000;
+
+    fn step_limit(&self) -> Option<usize> {
+        match self.max_steps {
+            None if !self.detect_loops => Some(Termination::DEFAULT_MAX_STEPS),
+            max => max,
         }
+    }
+}
 
-        match program
-            .get(ip as usize)
-            .ok_or_else(|| crate::Error::boxed(Error::InvalidInput))?
+struct Vm<'a> {
+    program: &'a [Instruction],
+    isa: &'a InstructionSet,
+    state: State,
+    output: Vec<i32>,
+    visited: Vec<bool>,
+    termination: Termination,
+}
+
+impl<'a> Vm<'a> {
+    fn new(program: &'a [Instruction], termination: Termination) -> Self {
+        Vm::with_isa(program, &BASE, termination)
+    }
+
+    fn with_isa(
+        program: &'a [Instruction],
+        isa: &'a InstructionSet,
+        termination: Termination,
+    ) -> Self {
+        Vm {
+            program,
+            isa,
+            state: State::default(),
+            output: Vec::new(),
+            visited: vec![false; program.len()],
+            termination,
+        }
+    }
+
+    /// Why the VM can't execute the instruction at `ip`, if it can't.
+    fn stopped(&self) -> Option<Res> {
+        let State { ip, acc, steps } = self.state;
+        if ip == self.program.len() as i32 {
+            Some(Res::Halt(acc))
+        } else if ip < 0 || ip > self.program.len() as i32 {
+            Some(Res::OutOfBounds { ip, acc })
+        } else if self.termination.detect_loops && self.visited[ip as usize] {
+            Some(Res::Loop(acc))
+        } else if self
+            .termination
+            .step_limit()
+            .is_some_and(|max| steps >= max)
         {
-            ("jmp", n) => ip += n,
-            ("acc", n) => {
-                acc += n;
-                ip += 1;
+            Some(Res::StepLimit(acc))
+        } else {
+            None
+        }
+    }
+
+    /// Executes the instruction at `ip`, or returns why that isn't possible.
+    fn step(&mut self) -> Option<Res> {
+        if let Some(res) = self.stopped() {
+            return Some(res);
+        }
+
+        let s = &mut self.state;
+        let (ip, acc, insn) = (s.ip, s.acc, self.program[s.ip as usize]);
+        let next = match insn {
+            Instruction::Acc(n) => acc.checked_add(n).map(|acc| (ip + 1, acc)),
+            Instruction::Jmp(n) => ip.checked_add(n).map(|ip| (ip, acc)),
+            Instruction::Nop(_) => Some((ip + 1, acc)),
+            Instruction::Ext {
+                op, args, arity, ..
+            } => {
+                let mut t = *s;
+                (self.isa.ops[op].exec)(&mut t, &args[..arity], &mut self.output)
+                    .map(|()| (t.ip, t.acc))
+            }
+        };
+        match next {
+            Some((ip, acc)) => {
+                s.ip = ip;
+                s.acc = acc;
+            }
+            None => return Some(Res::Overflow { ip, acc }),
+        }
+        self.visited[ip as usize] = true;
+        s.steps += 1;
+        None
+    }
+
+    fn run(&mut self) -> Res {
+        loop {
+            if let Some(res) = self.step() {
+                return res;
             }
-            ("nop", _) => ip += 1,
-            _ => return Err(crate::Error::boxed(Error::InvalidInput)),
         }
     }
 }
 
-fn parse(s: &str) -> crate::Result<Vec<(&str, i32)>> {
-    let prog = s
-        .lines()
-        .map(|l| -> crate::Result<_> {
-            let insn = l.trim_end_matches(|c: char| {
-                c.is_digit(10) || c == '-' || c == '+' || c.is_whitespace()
-            });
-            let num = l
-                .split(' ')
-                .nth(1)
-                .and_then(|w| w.parse().ok())
-                .ok_or_else(|| crate::Error::boxed(Error::InvalidInput))?;
-            Ok((insn, num))
+/// Where the debugger stops: when about to execute the instruction at an
+/// address, or when the accumulator changes to a value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Breakpoint {
+    Addr(i32),
+    Acc(i32),
+}
+
+impl FromStr for Breakpoint {
+    type Err = Box<dyn std::error::Error>;
+
+    /// Parses `ip=<addr>` or `acc=<value>`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (k, n) = s
+            .split_once('=')
+            .ok_or_else(|| crate::Error::boxed(Error::InvalidBreakpoint))?;
+        match k.trim() {
+            "ip" => Ok(Breakpoint::Addr(n.trim().parse()?)),
+            "acc" => Ok(Breakpoint::Acc(n.trim().parse()?)),
+            _ => Err(crate::Error::boxed(Error::InvalidBreakpoint)),
+        }
+    }
+}
+
+/// An arithmetic expression over the VM state, e.g. `acc - 2 * ip` or
+/// `-acc * 3`.
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Num(i64),
+    Ip,
+    Acc,
+    Steps,
+    Neg(Box<Expr>),
+    Add(Box<Expr>, Box<Expr>),
+    Sub(Box<Expr>, Box<Expr>),
+    Mul(Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    fn eval(&self, s: &State) -> i64 {
+        match self {
+            Expr::Num(n) => *n,
+            Expr::Ip => s.ip as i64,
+            Expr::Acc => s.acc as i64,
+            Expr::Steps => s.steps as i64,
+            Expr::Neg(a) => a.eval(s).wrapping_neg(),
+            Expr::Add(a, b) => a.eval(s).wrapping_add(b.eval(s)),
+            Expr::Sub(a, b) => a.eval(s).wrapping_sub(b.eval(s)),
+            Expr::Mul(a, b) => a.eval(s).wrapping_mul(b.eval(s)),
+        }
+    }
+
+    fn atom(ts: &mut std::iter::Peekable<std::vec::IntoIter<String>>) -> Option<Expr> {
+        let t = ts.next()?;
+        match t.as_str() {
+            "ip" => Some(Expr::Ip),
+            "acc" => Some(Expr::Acc),
+            "steps" => Some(Expr::Steps),
+            "-" => Some(Expr::Neg(Box::new(Expr::atom(ts)?))),
+            _ => t.parse().ok().map(Expr::Num),
+        }
+    }
+
+    fn term(ts: &mut std::iter::Peekable<std::vec::IntoIter<String>>) -> Option<Expr> {
+        let mut e = Expr::atom(ts)?;
+        while ts.peek().map(String::as_str) == Some("*") {
+            ts.next();
+            e = Expr::Mul(Box::new(e), Box::new(Expr::atom(ts)?));
+        }
+        Some(e)
+    }
+}
+
+impl FromStr for Expr {
+    type Err = Box<dyn std::error::Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens: Vec<String> = Vec::new();
+        for c in s.chars().filter(|c| !c.is_whitespace()) {
+            match (c, tokens.last_mut()) {
+                ('+' | '-' | '*', _) => tokens.push(c.to_string()),
+                (_, Some(t)) if !matches!(t.as_str(), "+" | "-" | "*") => t.push(c),
+                _ => tokens.push(c.to_string()),
+            }
+        }
+
+        let mut ts = tokens.into_iter().peekable();
+        let err = || crate::Error::boxed(Error::InvalidExpression);
+        let mut e = Expr::term(&mut ts).ok_or_else(err)?;
+        while let Some(op) = ts.next() {
+            let rhs = Box::new(Expr::term(&mut ts).ok_or_else(err)?);
+            e = match op.as_str() {
+                "+" => Expr::Add(Box::new(e), rhs),
+                "-" => Expr::Sub(Box::new(e), rhs),
+                _ => return Err(err()),
+            };
+        }
+        Ok(e)
+    }
+}
+
+/// Why the debugger handed back control.
+#[derive(Debug, Clone, PartialEq)]
+enum Stop {
+    Breakpoint(Breakpoint),
+    /// The watch with this index changed from the first value to the second.
+    Watch(usize, i64, i64),
+    Done(Res),
+}
+
+/// Runs a `Vm` an instruction at a time, stopping at breakpoints and when
+/// watched expressions change. The VM's own termination still applies, so a
+/// loop is reported as soon as an instruction would run twice.
+struct Debugger<'a> {
+    vm: Vm<'a>,
+    breakpoints: Vec<Breakpoint>,
+    watches: Vec<(Expr, i64)>,
+}
+
+impl<'a> Debugger<'a> {
+    fn new(vm: Vm<'a>) -> Self {
+        Debugger {
+            vm,
+            breakpoints: Vec::new(),
+            watches: Vec::new(),
+        }
+    }
+
+    fn watch(&mut self, e: Expr) {
+        let v = e.eval(&self.vm.state);
+        self.watches.push((e, v));
+    }
+
+    /// The breakpoint on the instruction about to run, if any. `step`
+    /// checks this once it has moved on, so a breakpoint on the very first
+    /// instruction has to be checked before anything runs.
+    fn at_breakpoint(&self) -> Option<Stop> {
+        self.breakpoints
+            .iter()
+            .find(|&&bp| bp == Breakpoint::Addr(self.vm.state.ip))
+            .map(|&bp| Stop::Breakpoint(bp))
+    }
+
+    /// Executes a single instruction and reports the first reason to stop
+    /// after it, if any.
+    fn step(&mut self) -> Option<Stop> {
+        let prev = self.vm.state;
+        if let Some(res) = self.vm.step() {
+            return Some(Stop::Done(res));
+        }
+        let s = self.vm.state;
+
+        let mut stop = None;
+        for (i, (e, v)) in self.watches.iter_mut().enumerate() {
+            let new = e.eval(&s);
+            if new != *v {
+                stop = stop.or(Some(Stop::Watch(i, *v, new)));
+                *v = new;
+            }
+        }
+        stop.or_else(|| {
+            self.breakpoints
+                .iter()
+                .find(|&&bp| bp == Breakpoint::Acc(s.acc) && prev.acc != s.acc)
+                .map(|&bp| Stop::Breakpoint(bp))
         })
-        .collect::<Result<_, _>>()?;
-    Ok(prog)
+        .or_else(|| self.at_breakpoint())
+    }
+
+    fn cont(&mut self) -> Stop {
+        loop {
+            if let Some(stop) = self.step() {
+                return stop;
+            }
+        }
+    }
 }
 
-pub fn part1(input: &str) -> crate::Result<i32> {
+/// Assembles source in the format of `Line`, of which the puzzle input is
+/// the special case without labels or comments.
+fn parse(s: &str) -> crate::Result<Vec<Instruction>> {
+    parse_with(s, &BASE)
+}
+
+/// Assembles source using the opcodes in `isa`.
+fn parse_with(s: &str, isa: &InstructionSet) -> crate::Result<Vec<Instruction>> {
+    let lines = parse_lines(s)?;
+
+    let mut labels = HashMap::new();
+    let mut addr = 0;
+    for (n, l) in lines.iter().enumerate() {
+        if let Some(label) = l.label {
+            if labels.insert(label, addr).is_some() {
+                return Err(crate::Error::boxed(Error::DuplicateLabel(
+                    n + 1,
+                    label.to_string(),
+                )));
+            }
+        }
+        addr += l.insn.is_some() as i32;
+    }
+
+    let mut program = Vec::new();
+    for (n, l) in lines.iter().enumerate() {
+        let (op, args) = match &l.insn {
+            Some(insn) => insn,
+            None => continue,
+        };
+        let mut resolved = Vec::new();
+        for arg in args {
+            resolved.push(match *arg {
+                Operand::Num(n) => n,
+                Operand::Label(label) => match labels.get(label) {
+                    Some(target) => target - program.len() as i32,
+                    None => {
+                        return Err(crate::Error::boxed(Error::UnknownLabel(
+                            n + 1,
+                            label.to_string(),
+                        )))
+                    }
+                },
+            });
+        }
+        let insn = isa.instruction(n + 1, op, &resolved)?;
+        program.push(insn);
+    }
+    Ok(program)
+}
+
+/// The program as source with a label `L<addr>` for every jump target in or
+/// just past the program, so that it assembles back to the same program.
+fn disassemble(program: &[Instruction]) -> String {
+    let len = program.len() as i32;
+    let target = |addr: usize, insn: &Instruction| match insn {
+        Instruction::Jmp(n) if (0..=len).contains(&(addr as i32 + n)) => Some(addr as i32 + n),
+        _ => None,
+    };
+    let mut targets = vec![false; program.len() + 1];
+    for (addr, insn) in program.iter().enumerate() {
+        if let Some(t) = target(addr, insn) {
+            targets[t as usize] = true;
+        }
+    }
+
+    let mut out = String::new();
+    for (addr, &is_target) in targets.iter().enumerate() {
+        if is_target {
+            out += &format!("L{}:\n", addr);
+        }
+        if let Some(insn) = program.get(addr) {
+            match target(addr, insn) {
+                Some(t) => out += &format!("    jmp L{}\n", t),
+                None => out += &format!("    {}\n", insn),
+            }
+        }
+    }
+    out
+}
+
+/// Reformats assembler source, keeping labels, operands and comments.
+pub fn format_source(input: &str) -> crate::Result<String> {
+    // Only well formed programs get formatted
+    parse(input)?;
+
+    let mut out = String::new();
+    for l in parse_lines(input)? {
+        out += &format!("{}\n", l);
+    }
+    Ok(out)
+}
+
+pub fn disassembly(input: &str) -> crate::Result<String> {
+    Ok(disassemble(&parse(input)?))
+}
+
+/// The assembled program with addresses and absolute jump targets, marking
+/// with `*` every instruction executed before the first run stops.
+pub fn listing(input: &str) -> crate::Result<String> {
+    let program = parse(input)?;
+    let mut vm = Vm::new(&program, Termination::default());
+    let res = vm.run();
+
+    let mut out = String::new();
+    for (addr, insn) in program.iter().enumerate() {
+        let mark = if vm.visited[addr] { '*' } else { ' ' };
+        out += &format!("{} {:04}  {}", mark, addr, insn);
+        if let Instruction::Jmp(n) = insn {
+            out += &format!("  -> {:04}", addr as i32 + n);
+        }
+        out += "\n";
+    }
+    out += &format!("{:?} after {} steps\n", res, vm.state.steps);
+    Ok(out)
+}
+
+/// Runs the program in `input` until it stops under `termination`.
+pub fn run_with(input: &str, termination: Termination) -> crate::Result<Res> {
+    let program = parse(input)?;
+    Ok(Vm::new(&program, termination).run())
+}
+
+/// Runs the program like `run_with`, writing each executed instruction to
+/// `out` as it goes, as its address, the instruction and the accumulator
+/// after it.
+pub fn run_traced(
+    input: &str,
+    termination: Termination,
+    out: &mut dyn io::Write,
+) -> crate::Result<Res> {
+    let program = parse(input)?;
+    let mut vm = Vm::new(&program, termination);
+    loop {
+        let ip = vm.state.ip;
+        if let Some(res) = vm.step() {
+            return Ok(res);
+        }
+        let insn = program[ip as usize].to_string();
+        writeln!(out, "{:04}  {:<8}  {}", ip, insn, vm.state.acc)?;
+    }
+}
+
+/// A debugger on the program in `input` with the comma separated
+/// `breakpoints` and `watches`, along with the source of each watch.
+fn debugger<'a>(
+    program: &'a [Instruction],
+    breakpoints: &str,
+    watches: &str,
+) -> crate::Result<(Debugger<'a>, Vec<String>)> {
+    let mut dbg = Debugger::new(Vm::new(program, Termination::default()));
+    for bp in breakpoints.split(',').filter(|s| !s.trim().is_empty()) {
+        dbg.breakpoints.push(bp.parse()?);
+    }
+    let watches: Vec<_> = watches
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect();
+    for w in &watches {
+        dbg.watch(w.parse()?);
+    }
+    Ok((dbg, watches))
+}
+
+/// The reason for `stop`, naming watches by their source, and the state.
+fn describe(stop: Option<&Stop>, s: &State, watches: &[String]) -> String {
+    let at = format!("ip={} acc={} steps={}", s.ip, s.acc, s.steps);
+    match stop {
+        Some(Stop::Watch(i, old, new)) => {
+            format!("Watch {}: {} -> {} at {}", watches[*i], old, new, at)
+        }
+        Some(stop) => format!("{:?} at {}", stop, at),
+        None => format!("Step at {}", at),
+    }
+}
+
+/// Runs the program under the debugger with the comma separated
+/// `breakpoints` and `watches`, listing every stop with the state there.
+pub fn debug(input: &str, breakpoints: &str, watches: &str) -> crate::Result<String> {
+    let program = parse(input)?;
+    let (mut dbg, watches) = debugger(&program, breakpoints, watches)?;
+
+    let mut out = String::new();
+    if let Some(stop) = dbg.at_breakpoint() {
+        out += &format!("{}\n", describe(Some(&stop), &dbg.vm.state, &watches));
+    }
+    loop {
+        let stop = dbg.cont();
+        out += &format!("{}\n", describe(Some(&stop), &dbg.vm.state, &watches));
+        if let Stop::Done(_) = stop {
+            return Ok(out);
+        }
+    }
+}
+
+/// Debugs the program interactively with commands read a line at a time
+/// from `r`: `step` or `s`, `continue` or `c`, `break <breakpoint>`, `watch
+/// <expr>`, `print <expr>` and `quit` or `q`. Each stop is written to `out`.
+/// The session ends with the program or the commands.
+pub fn debug_session(
+    input: &str,
+    breakpoints: &str,
+    watches: &str,
+    r: impl io::BufRead,
+    mut out: impl io::Write,
+) -> crate::Result<()> {
     let program = parse(input)?;
+    let (mut dbg, mut watches) = debugger(&program, breakpoints, watches)?;
+
+    if let Some(stop) = dbg.at_breakpoint() {
+        writeln!(out, "{}", describe(Some(&stop), &dbg.vm.state, &watches))?;
+    }
+    for l in r.lines() {
+        let l = l?;
+        let (cmd, arg) = l.trim().split_once(' ').unwrap_or((l.trim(), ""));
+        let stop = match cmd {
+            "s" | "step" => dbg.step(),
+            "c" | "continue" => Some(dbg.cont()),
+            "b" | "break" => {
+                match arg.parse() {
+                    Ok(bp) => dbg.breakpoints.push(bp),
+                    Err(e) => writeln!(out, "{}", e)?,
+                }
+                continue;
+            }
+            "w" | "watch" => {
+                match arg.parse() {
+                    Ok(e) => {
+                        dbg.watch(e);
+                        watches.push(arg.trim().to_string());
+                    }
+                    Err(e) => writeln!(out, "{}", e)?,
+                }
+                continue;
+            }
+            "p" | "print" => {
+                match arg.parse::<Expr>() {
+                    Ok(e) => writeln!(out, "{}", e.eval(&dbg.vm.state))?,
+                    Err(e) => writeln!(out, "{}", e)?,
+                }
+                continue;
+            }
+            "q" | "quit" => return Ok(()),
+            "" => continue,
+            _ => {
+                writeln!(out, "unknown command '{}'", cmd)?;
+                continue;
+            }
+        };
+
+        writeln!(out, "{}", describe(stop.as_ref(), &dbg.vm.state, &watches))?;
+        if let Some(Stop::Done(_)) = stop {
+            return Ok(());
+        }
+    }
+    Ok(())
+}
+
+/// Runs the program using the opcodes in `isa`, showing how it stopped and
+/// what it wrote.
+pub fn run_isa(
+    input: &str,
+    isa: &InstructionSet,
+    termination: Termination,
+) -> crate::Result<String> {
+    let program = parse_with(input, isa)?;
+    let mut vm = Vm::with_isa(&program, isa, termination);
+    let res = vm.run();
+    Ok(format!("{:?} with output {:?}", res, vm.output))
+}
+
+/// Runs the program with the `mul`, `jz` and `out` extensions.
+pub fn run_extended(input: &str, termination: Termination) -> crate::Result<String> {
+    run_isa(input, &InstructionSet::extended(), termination)
+}
 
-    match run(&program)? {
+pub fn part1(input: &str) -> crate::Result<i32> {
+    match run_with(input, Termination::default())? {
         Res::Loop(acc) => Ok(acc),
         _ => Err(crate::Error::boxed(Error::InvalidInput)),
     }
 }
 
+/// The control flow graph of a program, with one node per instruction plus
+/// one for the address just past the end, where it terminates. Jumps out of
+/// the program any other way lead nowhere. Only programs in the base set
+/// are supported, since where an extension opcode goes isn't known.
+struct Cfg<'a> {
+    program: &'a [Instruction],
+}
+
+impl<'a> Cfg<'a> {
+    fn node(&self, addr: i32) -> Option<usize> {
+        if (0..=self.program.len() as i32).contains(&addr) {
+            Some(addr as usize)
+        } else {
+            None
+        }
+    }
+
+    /// Where control goes after `addr` as the program is, and with the
+    /// instruction there flipped.
+    fn successors(&self, addr: usize) -> (Option<usize>, Option<usize>) {
+        let insn = self.program[addr];
+        (
+            self.node(insn.next(addr)),
+            insn.flipped().and_then(|f| self.node(f.next(addr))),
+        )
+    }
+
+    /// Which nodes run into the end without any flips, found by walking the
+    /// edges backwards from it.
+    fn reaches_end(&self) -> Vec<bool> {
+        let len = self.program.len();
+        let mut preds = vec![Vec::new(); len + 1];
+        for addr in 0..len {
+            if let (Some(next), _) = self.successors(addr) {
+                preds[next].push(addr);
+            }
+        }
+
+        let mut reaches = vec![false; len + 1];
+        reaches[len] = true;
+        let mut todo = vec![len];
+        while let Some(n) = todo.pop() {
+            for &p in &preds[n] {
+                if !reaches[p] {
+                    reaches[p] = true;
+                    todo.push(p);
+                }
+            }
+        }
+        reaches
+    }
+
+    /// The one instruction to flip so the program terminates: the first one
+    /// on the looping path whose flipped successor runs into the end.
+    fn single_flip(&self) -> Option<usize> {
+        let reaches = self.reaches_end();
+        let mut visited = vec![false; self.program.len()];
+        let mut addr = 0;
+        while addr < self.program.len() && !visited[addr] {
+            visited[addr] = true;
+            match self.successors(addr) {
+                (_, Some(next)) if reaches[next] => return Some(addr),
+                (Some(next), _) => addr = next,
+                (None, _) => break,
+            }
+        }
+        None
+    }
+
+    /// The fewest instructions to flip so the program terminates, found by a
+    /// shortest path search where following an instruction costs nothing and
+    /// flipping it costs one. A shortest path never visits an instruction
+    /// twice, so each flip on it is applied once and for all.
+    fn min_flips(&self) -> Option<Vec<usize>> {
+        let len = self.program.len();
+        let mut dist = vec![usize::MAX; len + 1];
+        let mut via: Vec<Option<(usize, bool)>> = vec![None; len + 1];
+        let mut todo = VecDeque::new();
+        dist[0] = 0;
+        todo.push_back(0);
+        while let Some(n) = todo.pop_front() {
+            if n == len {
+                break;
+            }
+
+            let (next, flipped) = self.successors(n);
+            for (m, cost) in next
+                .map(|m| (m, 0))
+                .into_iter()
+                .chain(flipped.map(|m| (m, 1)))
+            {
+                if dist[n] + cost < dist[m] {
+                    dist[m] = dist[n] + cost;
+                    via[m] = Some((n, cost == 1));
+                    if cost == 0 {
+                        todo.push_front(m);
+                    } else {
+                        todo.push_back(m);
+                    }
+                }
+            }
+        }
+
+        if dist[len] == usize::MAX {
+            return None;
+        }
+        let mut flips = Vec::new();
+        let mut n = len;
+        while let Some((prev, flipped)) = via[n] {
+            if flipped {
+                flips.push(prev);
+            }
+            n = prev;
+        }
+        flips.reverse();
+        Some(flips)
+    }
+}
+
+/// Runs the program with the instructions at `flips` flipped.
+fn run_flipped(program: &[Instruction], flips: &[usize]) -> Res {
+    let mut program = program.to_vec();
+    for &addr in flips {
+        if let Some(f) = program[addr].flipped() {
+            program[addr] = f;
+        }
+    }
+    Vm::new(&program, Termination::default()).run()
+}
+
+/// The fewest instructions to flip for the program to terminate, and how
+/// it ends up.
+pub fn repair(input: &str) -> crate::Result<String> {
+    let program = parse(input)?;
+    let flips = Cfg { program: &program }
+        .min_flips()
+        .ok_or_else(|| crate::Error::boxed(Error::InvalidInput))?;
+
+    let addrs: Vec<_> = flips.iter().map(|a| format!("{:04}", a)).collect();
+    Ok(format!(
+        "Flip {} instructions [{}]: {:?}",
+        flips.len(),
+        addrs.join(", "),
+        run_flipped(&program, &flips)
+    ))
+}
+
 pub fn part2(input: &str) -> crate::Result<i32> {
-    let mut program = parse(input)?;
+    let program = parse(input)?;
+    let addr = Cfg { program: &program }
+        .single_flip()
+        .ok_or_else(|| crate::Error::boxed(Error::InvalidInput))?;
+
+    match run_flipped(&program, &[addr]) {
+        Res::Halt(acc) => Ok(acc),
+        _ => Err(crate::Error::boxed(Error::InvalidInput)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EX: &str = "nop +0
+acc +1
+jmp +4
+acc +3
+jmp -3
+acc -99
+acc +1
+jmp -4
+acc +6";
+
+    #[test]
+    fn vm() {
+        assert_eq!(part1(EX).unwrap(), 5);
+        assert_eq!(part2(EX).unwrap(), 8);
+
+        let program = parse(EX).unwrap();
+        let mut vm = Vm::new(&program, Termination::default());
+        assert_eq!(vm.step(), None);
+        assert_eq!(vm.step(), None);
+        assert_eq!(
+            vm.state,
+            State {
+                ip: 2,
+                acc: 1,
+                steps: 2
+            }
+        );
+        assert_eq!(vm.run(), Res::Loop(5));
+        assert_eq!(vm.state.steps, 7);
 
-    for i in 0..program.len() {
-        program[i] = match program[i] {
-            ("jmp", n) => ("nop", n),
-            ("nop", n) => ("jmp", n),
-            _ => continue,
+        let limited = Termination {
+            detect_loops: false,
+            max_steps: Some(10),
         };
+        assert_eq!(run_with(EX, limited).unwrap(), Res::StepLimit(7));
+        assert_eq!(
+            run_with("nop +0\njmp -2", Termination::default()).unwrap(),
+            Res::OutOfBounds { ip: -1, acc: 0 }
+        );
+        assert_eq!(
+            run_with("acc +2\njmp +1", Termination::default()).unwrap(),
+            Res::Halt(2)
+        );
+        assert!(parse("acc +1\nmul 2").is_err());
+        assert!(parse("acc").is_err());
 
-        if let Res::Halt(acc) = run(&program)? {
-            return Ok(acc);
-        }
+        let forever = Termination {
+            detect_loops: false,
+            max_steps: None,
+        };
+        assert_eq!(run_with("jmp +0", forever).unwrap(), Res::StepLimit(0));
+        let big = format!("acc {}\nacc +1", i32::MAX);
+        assert_eq!(
+            run_with(&big, Termination::default()).unwrap(),
+            Res::Overflow {
+                ip: 1,
+                acc: i32::MAX
+            }
+        );
+        let far = format!("nop +0\njmp {}", i32::MAX);
+        assert_eq!(
+            run_with(&far, Termination::default()).unwrap(),
+            Res::Overflow { ip: 1, acc: 0 }
+        );
+    }
+
+    #[test]
+    fn assembler() {
+        let src = "# count to three
+start:  acc +1   # bump
+        jmp check
+loop: jmp start
+check:
+    acc -3
+    nop loop
+    acc +3
+    jmp done
+done:";
+        let program = parse(src).unwrap();
+        assert_eq!(
+            program,
+            [
+                Instruction::Acc(1),
+                Instruction::Jmp(2),
+                Instruction::Jmp(-2),
+                Instruction::Acc(-3),
+                Instruction::Nop(-2),
+                Instruction::Acc(3),
+                Instruction::Jmp(1),
+            ]
+        );
 
-        program[i] = match program[i] {
-            ("jmp", n) => ("nop", n),
-            ("nop", n) => ("jmp", n),
-            _ => unreachable!(),
+        let dis = disassemble(&program);
+        assert_eq!(
+            dis,
+            "L0:\n    acc +1\n    jmp L3\n    jmp L0\nL3:\n    acc -3\n    nop -2\n    acc +3\n    jmp L7\nL7:\n"
+        );
+        assert_eq!(parse(&dis).unwrap(), program);
+        assert_eq!(
+            parse(&disassemble(&parse(EX).unwrap())).unwrap(),
+            parse(EX).unwrap()
+        );
+
+        let formatted = format_source(src).unwrap();
+        assert_eq!(
+            formatted.lines().take(4).collect::<Vec<_>>(),
+            [
+                "# count to three",
+                "start:",
+                "    acc +1  # bump",
+                "    jmp check"
+            ]
+        );
+        assert_eq!(format_source(&formatted).unwrap(), formatted);
+        assert_eq!(parse(&formatted).unwrap(), program);
+
+        let lst = listing(EX).unwrap();
+        assert!(lst.starts_with("* 0000  nop +0\n* 0001  acc +1\n* 0002  jmp +4  -> 0006\n"));
+        assert!(lst.contains("  0005  acc -99\n"));
+        assert!(lst.ends_with("Loop(5) after 7 steps\n"));
+
+        assert!(parse("jmp nowhere").is_err());
+        assert!(parse("a:\na: nop +0").is_err());
+        assert!(parse("acc +1 +2").is_err());
+        assert!(parse("1a: nop +0").is_err());
+    }
+
+    #[test]
+    fn debugger() {
+        let program = parse(EX).unwrap();
+        let mut dbg = Debugger::new(Vm::new(&program, Termination::default()));
+        dbg.breakpoints.push("ip=4".parse().unwrap());
+        dbg.breakpoints.push("acc = 1".parse().unwrap());
+        dbg.watch("acc * 2 - ip".parse().unwrap());
+
+        let state = State {
+            ip: 3,
+            acc: 5,
+            steps: 0,
         };
+        let eval = |s: &str| s.parse::<Expr>().unwrap().eval(&state);
+        assert_eq!(eval("acc * -1"), -5);
+        assert_eq!(eval("-1"), -1);
+        assert_eq!(eval("-acc - -ip"), -2);
+        assert_eq!(eval("2 - -3 * ip"), 11);
+        assert!("acc * -".parse::<Expr>().is_err());
+
+        assert_eq!(dbg.step(), Some(Stop::Watch(0, 0, -1)));
+        assert_eq!(dbg.step(), Some(Stop::Watch(0, -1, 0)));
+        assert_eq!(dbg.cont(), Stop::Watch(0, 0, -4));
+        assert_eq!(dbg.cont(), Stop::Watch(0, -4, -3));
+        assert_eq!(dbg.vm.state.ip, 7);
+        assert_eq!(dbg.cont(), Stop::Watch(0, -3, 1));
+        assert_eq!(dbg.cont(), Stop::Watch(0, 1, 6));
+        assert_eq!(dbg.cont(), Stop::Watch(0, 6, 9));
+        assert_eq!(dbg.cont(), Stop::Done(Res::Loop(5)));
+
+        let mut dbg = Debugger::new(Vm::new(&program, Termination::default()));
+        dbg.breakpoints.push(Breakpoint::Addr(4));
+        assert_eq!(dbg.cont(), Stop::Breakpoint(Breakpoint::Addr(4)));
+        assert_eq!(dbg.vm.state.acc, 5);
+        assert_eq!(dbg.cont(), Stop::Done(Res::Loop(5)));
+
+        assert_eq!(
+            Expr::from_str("acc - 2*ip + 3").unwrap().eval(&State {
+                ip: 4,
+                acc: 10,
+                steps: 0
+            }),
+            5
+        );
+        assert!(Expr::from_str("acc +").is_err());
+        assert!(Expr::from_str("pc").is_err());
+        assert!(Breakpoint::from_str("ip:3").is_err());
+
+        let out = debug(EX, "acc=5", "").unwrap();
+        assert_eq!(
+            out,
+            "Breakpoint(Acc(5)) at ip=4 acc=5 steps=6\nDone(Loop(5)) at ip=1 acc=5 steps=7\n"
+        );
+
+        assert!(debug(EX, "ip=0", "")
+            .unwrap()
+            .starts_with("Breakpoint(Addr(0)) at ip=0 acc=0 steps=0\n"));
+
+        let cmds = "s\nb ip=4\nb ip\nw acc\nc\np acc * 2\nbogus\nc\nc\nc\nc";
+        let mut out = Vec::new();
+        debug_session(EX, "", "", cmds.as_bytes(), &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap().lines().collect::<Vec<_>>(),
+            [
+                "Step at ip=1 acc=0 steps=1",
+                "invalid breakpoint, expected ip=<n> or acc=<n>",
+                "Watch acc: 0 -> 1 at ip=2 acc=1 steps=2",
+                "2",
+                "unknown command 'bogus'",
+                "Watch acc: 1 -> 2 at ip=7 acc=2 steps=4",
+                "Watch acc: 2 -> 5 at ip=4 acc=5 steps=6",
+                "Done(Loop(5)) at ip=1 acc=5 steps=7",
+            ]
+        );
+
+        let mut trace = Vec::new();
+        let res = run_traced(EX, Termination::default(), &mut trace).unwrap();
+        assert_eq!(res, Res::Loop(5));
+        let trace = String::from_utf8(trace).unwrap();
+        assert_eq!(trace.lines().count(), 7);
+        assert_eq!(trace.lines().nth(2), Some("0002  jmp +4    1"));
     }
 
-    Err(crate::Error::boxed(Error::InvalidInput))
+    #[test]
+    fn repairs() {
+        let program = parse(EX).unwrap();
+        let cfg = Cfg { program: &program };
+        assert_eq!(cfg.single_flip(), Some(7));
+
+        // Flipping 1 works too, but 3 runs first
+        let program = parse("jmp +3\njmp -1\njmp +2\njmp -2").unwrap();
+        assert_eq!(Cfg { program: &program }.single_flip(), Some(3));
+        assert_eq!(cfg.min_flips(), Some(vec![7]));
+        assert_eq!(repair(EX).unwrap(), "Flip 1 instructions [0007]: Halt(8)");
+
+        let halting = parse("acc +1\njmp +1").unwrap();
+        assert_eq!(Cfg { program: &halting }.min_flips(), Some(vec![]));
+
+        // Each jmp +0 spins on its own, so both have to become nops
+        let two = "jmp +0\nacc +2\njmp +0\nacc +3";
+        assert!(part2(two).is_err());
+        assert_eq!(
+            repair(two).unwrap(),
+            "Flip 2 instructions [0000, 0002]: Halt(5)"
+        );
+        assert_eq!(
+            repair("jmp +0\nacc +1\njmp -1").unwrap(),
+            "Flip 2 instructions [0000, 0002]: Halt(1)"
+        );
+    }
+
+    #[test]
+    fn extensions() {
+        // Scales the accumulator, then brings it back to zero to leave
+        let src = "acc +3
+out
+mul 4
+out
+jz done
+acc -12
+jz done
+jmp -8
+done: out";
+        let isa = InstructionSet::extended();
+        let program = parse_with(src, &isa).unwrap();
+        assert_eq!(program[8].to_string(), "out");
+        assert_eq!(program[4].to_string(), "jz +4");
+
+        let mut vm = Vm::with_isa(&program, &isa, Termination::default());
+        assert_eq!(vm.run(), Res::Halt(0));
+        assert_eq!(vm.output, [3, 12, 0]);
+        assert_eq!(
+            run_extended(src, Termination::default()).unwrap(),
+            "Halt(0) with output [3, 12, 0]"
+        );
+        assert!(parse(&disassemble(&program)).is_err());
+        assert_eq!(parse_with(&disassemble(&program), &isa).unwrap(), program);
+
+        let err = |src, isa| format!("{}", parse_with(src, isa).unwrap_err());
+        assert_eq!(
+            err("acc +1\nmul 2", &BASE),
+            "unknown opcode 'mul' on line 2"
+        );
+        assert_eq!(
+            err("nop +0\n\nout 1", &isa),
+            "wrong number of operands for 'out' on line 3"
+        );
+        assert_eq!(
+            err("jmp +1 +2", &isa),
+            "wrong number of operands for 'jmp' on line 1"
+        );
+
+        let mut custom = InstructionSet::default();
+        assert!(custom.register("acc", 1, |_, _, _| Some(())).is_err());
+        assert!(custom
+            .register("wide", MAX_ARITY + 1, |_, _, _| Some(()))
+            .is_err());
+        custom
+            .register("add", 2, |s, args, _| {
+                s.acc = s.acc.checked_add(args[0])?.checked_add(args[1])?;
+                s.ip += 1;
+                Some(())
+            })
+            .unwrap();
+        assert!(custom.register("add", 2, |_, _, _| Some(())).is_err());
+        let program = parse_with("add +2 +3\nadd -1 +0", &custom).unwrap();
+        assert_eq!(
+            Vm::with_isa(&program, &custom, Termination::default()).run(),
+            Res::Halt(4)
+        );
+        assert_eq!(
+            run_isa("add +2 +3\nout", &custom, Termination::default())
+                .unwrap_err()
+                .to_string(),
+            "unknown opcode 'out' on line 2"
+        );
+        let big = format!("acc {}\nmul 2", i32::MAX);
+        assert_eq!(
+            run_extended(&big, Termination::default()).unwrap(),
+            format!("Overflow {{ ip: 1, acc: {} }} with output []", i32::MAX)
+        );
+    }
 }
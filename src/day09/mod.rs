@@ -1,62 +1,215 @@
-use std::cmp::Ordering;
-use std::collections::VecDeque;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{BufRead, Write};
 
 #[derive(Debug)]
 enum Error {
     InvalidInput,
+    InvalidLine(usize),
 }
 
-type ValidSet = VecDeque<Vec<i64>>;
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidInput => write!(f, "invalid input"),
+            Error::InvalidLine(n) => write!(f, "invalid number on line {}", n),
+        }
+    }
+}
 
-pub fn part1(input: &str) -> crate::Result<i64> {
-    let nums = input
+/// The last `len` numbers of a stream in a ring buffer, with how often each
+/// value occurs among them, so checking whether a number is the sum of two
+/// of them takes `len` lookups and moving on takes constant time.
+struct Window {
+    nums: Vec<i64>,
+    len: usize,
+    next: usize,
+    counts: HashMap<i64, usize>,
+}
+
+impl Window {
+    fn new(len: usize) -> crate::Result<Self> {
+        if len < 2 {
+            return Err(crate::Error::boxed(Error::InvalidInput));
+        }
+        Ok(Window {
+            nums: Vec::with_capacity(len),
+            len,
+            next: 0,
+            counts: HashMap::new(),
+        })
+    }
+
+    fn is_full(&self) -> bool {
+        self.nums.len() == self.len
+    }
+
+    fn is_sum(&self, n: i64) -> bool {
+        self.nums.iter().any(|&a| match n.checked_sub(a) {
+            Some(b) if b == a => self.counts[&a] > 1,
+            Some(b) => self.counts.contains_key(&b),
+            None => false,
+        })
+    }
+
+    /// Checks `n` against the window and then slides it in. Numbers in the
+    /// preamble are always valid.
+    fn push(&mut self, n: i64) -> bool {
+        let valid = !self.is_full() || self.is_sum(n);
+
+        if self.is_full() {
+            let old = std::mem::replace(&mut self.nums[self.next], n);
+            let cnt = self.counts.get_mut(&old).unwrap();
+            *cnt -= 1;
+            if *cnt == 0 {
+                self.counts.remove(&old);
+            }
+            self.next = (self.next + 1) % self.nums.len();
+        } else {
+            self.nums.push(n);
+        }
+        *self.counts.entry(n).or_insert(0) += 1;
+
+        valid
+    }
+}
+
+fn parse(input: &str) -> crate::Result<Vec<i64>> {
+    Ok(input
         .lines()
         .map(|l| l.parse())
-        .collect::<Result<Vec<i64>, _>>()?;
-    const LEN: usize = 25;
-    let mut valid: ValidSet = vec![Vec::new(); LEN - 1].into();
+        .collect::<Result<Vec<i64>, _>>()?)
+}
 
-    for i in 0..LEN - 1 {
-        valid[i].extend(nums[i + 1..LEN].iter().map(|nn| nums[i] + nn));
+/// The first number after the preamble of `len` numbers that isn't the sum
+/// of two different numbers among the `len` before it.
+fn first_invalid(nums: &[i64], len: usize) -> crate::Result<i64> {
+    let mut w = Window::new(len)?;
+    if let Some(&n) = nums.iter().find(|&&n| !w.push(n)) {
+        return Ok(n);
     }
+    Err(crate::Error::boxed(Error::InvalidInput))
+}
 
-    for w in nums.windows(LEN).skip(1) {
-        let n = w.last().unwrap();
-        if !valid.iter().any(|v| v.contains(n)) {
-            return Ok(*n);
-        }
+/// The contiguous range of at least two numbers adding up to `target`, found
+/// by sliding both ends forward. That only works as long as adding a number
+/// never makes the sum smaller, so the numbers must not be negative.
+fn contiguous_range(nums: &[i64], target: i64) -> crate::Result<&[i64]> {
+    if nums.iter().any(|&n| n < 0) {
+        return Err(crate::Error::boxed(Error::InvalidInput));
+    }
 
-        valid.pop_front();
-        valid.push_back(Vec::new());
-        for i in 0..LEN - 1 {
-            valid[i].push(n + w[i]);
+    let (mut lo, mut sum) = (0, 0);
+    for hi in 0..nums.len() {
+        sum += nums[hi];
+        while sum > target && lo < hi {
+            sum -= nums[lo];
+            lo += 1;
+        }
+        if sum == target && lo < hi {
+            return Ok(&nums[lo..=hi]);
         }
     }
 
     Err(crate::Error::boxed(Error::InvalidInput))
 }
 
-pub fn part2(input: &str) -> crate::Result<i64> {
-    let nums = input
-        .lines()
-        .map(|l| l.parse())
-        .collect::<Result<Vec<i64>, _>>()?;
-    const INVALID: i64 = 41682220;
-
-    for start in 0..nums.len() {
-        let mut sum = nums[start];
-        for i in start + 1..nums.len() {
-            sum += nums[i];
-            match sum.cmp(&INVALID) {
-                Ordering::Equal => {
-                    return Ok(nums[start..=i].iter().min().unwrap()
-                        + nums[start..=i].iter().max().unwrap())
-                }
-                Ordering::Greater => break,
-                _ => (),
-            }
+/// The first invalid number with a preamble of `len`.
+pub fn invalid_number(input: &str, len: usize) -> crate::Result<i64> {
+    first_invalid(&parse(input)?, len)
+}
+
+/// The sum of the smallest and largest number in the contiguous range that
+/// adds up to the first invalid number with a preamble of `len`.
+pub fn weakness(input: &str, len: usize) -> crate::Result<i64> {
+    let nums = parse(input)?;
+    let range = contiguous_range(&nums, first_invalid(&nums, len)?)?;
+
+    Ok(range.iter().min().unwrap() + range.iter().max().unwrap())
+}
+
+/// Checks a stream of numbers of any length a line at a time, writing the
+/// line number and value of every invalid number to `out`. Returns how many
+/// there were.
+pub fn validate_stream(r: impl BufRead, len: usize, mut out: impl Write) -> crate::Result<usize> {
+    let mut w = Window::new(len)?;
+    let mut invalid = 0;
+    for (i, l) in r.lines().enumerate() {
+        let n = l?
+            .trim()
+            .parse()
+            .map_err(|_| crate::Error::boxed(Error::InvalidLine(i + 1)))?;
+        if !w.push(n) {
+            writeln!(out, "{}: {}", i + 1, n)?;
+            invalid += 1;
         }
     }
+    Ok(invalid)
+}
 
-    Err(crate::Error::boxed(Error::InvalidInput))
+pub fn part1(input: &str) -> crate::Result<i64> {
+    invalid_number(input, 25)
+}
+
+pub fn part2(input: &str) -> crate::Result<i64> {
+    weakness(input, 25)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EX: &str = "35
+20
+15
+25
+47
+40
+62
+55
+65
+95
+102
+117
+150
+182
+127
+219
+299
+277
+309
+576";
+
+    #[test]
+    fn preamble() {
+        assert_eq!(invalid_number(EX, 5).unwrap(), 127);
+        assert_eq!(weakness(EX, 5).unwrap(), 62);
+        assert_eq!(contiguous_range(&[1, 2, 3, 4], 7).unwrap(), [3, 4]);
+        assert!(contiguous_range(&[1, 2, 3, 4], 4).is_err());
+        assert!(contiguous_range(&[5, -1, 2], 6).is_err());
+        assert!(invalid_number(EX, 1).is_err());
+        assert!(invalid_number(EX, 20).is_err());
+    }
+
+    #[test]
+    fn stream() {
+        let mut out = Vec::new();
+        assert_eq!(validate_stream(EX.as_bytes(), 5, &mut out).unwrap(), 1);
+        assert_eq!(out, b"15: 127\n");
+
+        let mut out = Vec::new();
+        let s = "1\n2\n3\n4\n6\n8\n14\n100\n1\n";
+        assert_eq!(validate_stream(s.as_bytes(), 2, &mut out).unwrap(), 5);
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "4: 4\n5: 6\n6: 8\n8: 100\n9: 1\n"
+        );
+
+        // A number twice only makes a sum with itself while both are in the
+        // window
+        let mut w = Window::new(2).unwrap();
+        assert!(w.push(3) && w.push(3) && w.push(6) && !w.push(6));
+        assert!(Window::new(1).is_err());
+        assert!(validate_stream("1\nx".as_bytes(), 2, Vec::new()).is_err());
+    }
 }
@@ -1,15 +1,70 @@
 use std::collections::HashMap;
+use std::fmt;
 
 #[derive(Debug)]
 enum Error {
     InvalidInput,
+    Overflow,
 }
 
-pub fn part1(input: &str) -> crate::Result<i32> {
-    let mut chain: Vec<i32> = input.lines().map(str::parse).collect::<Result<_, _>>()?;
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidInput => write!(f, "invalid input"),
+            Error::Overflow => write!(f, "overflow"),
+        }
+    }
+}
+
+/// The outlet, all adapters and the device's built-in adapter, which is
+/// rated `max_step` above the highest adapter, in order.
+fn chain(input: &str, max_step: u32) -> crate::Result<Vec<u32>> {
+    if max_step == 0 {
+        return Err(crate::Error::boxed(Error::InvalidInput));
+    }
+
+    let mut chain: Vec<u32> = input.lines().map(str::parse).collect::<Result<_, _>>()?;
+    let device = chain
+        .iter()
+        .max()
+        .map_or(Some(max_step), |max| max.checked_add(max_step))
+        .ok_or_else(|| crate::Error::boxed(Error::Overflow))?;
     chain.push(0); // outlet joltage
-    chain.push(chain.iter().max().unwrap() + 3); // built-in adapter
+    chain.push(device); // built-in adapter
     chain.sort_unstable();
+    Ok(chain)
+}
+
+/// Counts the ways to get from the outlet to the device, where an adapter
+/// takes any input from 1 to `max_step` jolts below its rating.
+/// `ways[i]` is the number of ways to reach `chain[i]`, summed over the
+/// adapters within reach below it.
+fn count_arrangements(chain: &[u32], max_step: u32) -> crate::Result<u128> {
+    let mut ways = vec![0u128; chain.len()];
+    ways[0] = 1;
+    for i in 1..chain.len() {
+        for j in (0..i).rev() {
+            let step = chain[i] - chain[j];
+            if step > max_step {
+                break;
+            }
+            if step > 0 {
+                ways[i] = ways[i]
+                    .checked_add(ways[j])
+                    .ok_or_else(|| crate::Error::boxed(Error::Overflow))?;
+            }
+        }
+    }
+    Ok(*ways.last().unwrap())
+}
+
+/// The number of adapter arrangements with steps of up to `max_step` jolts.
+pub fn arrangements(input: &str, max_step: u32) -> crate::Result<u128> {
+    count_arrangements(&chain(input, max_step)?, max_step)
+}
+
+pub fn part1(input: &str) -> crate::Result<i32> {
+    let chain = chain(input, 3)?;
 
     let mut diffs = HashMap::new();
     for ns in chain.windows(2) {
@@ -25,26 +80,35 @@ pub fn part1(input: &str) -> crate::Result<i32> {
     Ok(ones * threes)
 }
 
-pub fn part2(input: &str) -> crate::Result<i64> {
-    let mut chain: Vec<i32> = input.lines().map(str::parse).collect::<Result<_, _>>()?;
-    chain.push(0); // outlet joltage
-    chain.push(chain.iter().max().unwrap() + 3); // built-in adapter
-    chain.sort_unstable();
+pub fn part2(input: &str) -> crate::Result<u128> {
+    arrangements(input, 3)
+}
 
-    let mut seqs = vec![0];
-    for d in chain.windows(2).map(|ns| ns[1] - ns[0]) {
-        if d == 1 {
-            *seqs.last_mut().unwrap() += 1;
-        } else {
-            seqs.push(0);
-        }
-    }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EX: &str = "16\n10\n15\n5\n1\n11\n7\n19\n6\n12\n4";
 
-    let n = seqs.iter().fold(1i64, |acc, n| match n {
-        2 => acc * 2,
-        3 => acc * 4,
-        4 => acc * 7,
-        _ => acc,
-    });
-    Ok(n)
+    #[test]
+    fn arrange() {
+        assert_eq!(part1(EX).unwrap(), 35);
+        assert_eq!(part2(EX).unwrap(), 8);
+
+        // Runs of five one-jolt steps and two-jolt gaps both matter
+        assert_eq!(part2("1\n2\n3\n4\n5").unwrap(), 13);
+        assert_eq!(part2("2\n4\n5").unwrap(), 2);
+        assert_eq!(part2("4").unwrap(), 0);
+        assert_eq!(arrangements("4", 4).unwrap(), 1);
+        assert_eq!(arrangements("1\n2\n3\n4\n5", 1).unwrap(), 1);
+        assert_eq!(arrangements("1\n2\n3\n4\n5", 5).unwrap(), 16);
+
+        let many: Vec<_> = (1..=100).map(|n: u32| n.to_string()).collect();
+        assert_eq!(arrangements(&many.join("\n"), 100).unwrap(), 1 << 99);
+        let more: Vec<_> = (1..=150).map(|n: u32| n.to_string()).collect();
+        assert!(arrangements(&more.join("\n"), 150).is_err());
+        assert!(arrangements(EX, 0).is_err());
+        assert!(arrangements(EX, u32::MAX).is_err());
+        assert_eq!(arrangements("", 3).unwrap(), 1);
+    }
 }
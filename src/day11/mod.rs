@@ -2,6 +2,8 @@ use std::collections::HashMap;
 use std::iter;
 use std::str::FromStr;
 
+use crate::viz::{Cell, Frame, Rgb};
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 enum Space {
     Floor,
@@ -28,24 +30,28 @@ impl Grid {
             .unwrap_or(&Space::Floor)
     }
 
-    fn stabilize_p1(&mut self) {
+    fn step_p1(&mut self) -> bool {
         let mut m = HashMap::new();
-        while m != self.map {
-            for (k, v) in &self.map {
-                let num_occupied = directions()
-                    .map(|d| self.look_p1(*k, d))
-                    .filter(|&s| s == Space::Occupied)
-                    .count();
-
-                match (v, num_occupied) {
-                    (Space::Vacant, 0) => m.insert(*k, Space::Occupied),
-                    (Space::Occupied, 4..=8) => m.insert(*k, Space::Vacant),
-                    _ => m.insert(*k, *v),
-                };
-            }
-
-            std::mem::swap(&mut m, &mut self.map);
+        for (k, v) in &self.map {
+            let num_occupied = directions()
+                .map(|d| self.look_p1(*k, d))
+                .filter(|&s| s == Space::Occupied)
+                .count();
+
+            match (v, num_occupied) {
+                (Space::Vacant, 0) => m.insert(*k, Space::Occupied),
+                (Space::Occupied, 4..=8) => m.insert(*k, Space::Vacant),
+                _ => m.insert(*k, *v),
+            };
         }
+
+        let changed = m != self.map;
+        self.map = m;
+        changed
+    }
+
+    fn stabilize_p1(&mut self) {
+        while self.step_p1() {}
     }
 
     fn look_p2(&self, mut pos: (i32, i32), dir: (i32, i32)) -> Space {
@@ -62,24 +68,46 @@ impl Grid {
         .unwrap()
     }
 
-    fn stabilize_p2(&mut self) {
+    fn step_p2(&mut self) -> bool {
         let mut m = HashMap::new();
-        while m != self.map {
-            for (k, v) in &self.map {
-                let num_occupied = directions()
-                    .map(|d| self.look_p2(*k, d))
-                    .filter(|&s| s == Space::Occupied)
-                    .count();
-
-                match (v, num_occupied) {
-                    (Space::Vacant, 0) => m.insert(*k, Space::Occupied),
-                    (Space::Occupied, 5..=8) => m.insert(*k, Space::Vacant),
-                    _ => m.insert(*k, *v),
-                };
-            }
+        for (k, v) in &self.map {
+            let num_occupied = directions()
+                .map(|d| self.look_p2(*k, d))
+                .filter(|&s| s == Space::Occupied)
+                .count();
+
+            match (v, num_occupied) {
+                (Space::Vacant, 0) => m.insert(*k, Space::Occupied),
+                (Space::Occupied, 5..=8) => m.insert(*k, Space::Vacant),
+                _ => m.insert(*k, *v),
+            };
+        }
 
-            std::mem::swap(&mut m, &mut self.map);
+        let changed = m != self.map;
+        self.map = m;
+        changed
+    }
+
+    fn stabilize_p2(&mut self) {
+        while self.step_p2() {}
+    }
+}
+
+impl Grid {
+    fn frame(&self) -> Frame {
+        let h = self.map.keys().map(|p| p.0 + 1).max().unwrap_or(0) as usize;
+        let w = self.map.keys().map(|p| p.1 + 1).max().unwrap_or(0) as usize;
+
+        let mut frame = Frame::new(w, h);
+        for (p, s) in &self.map {
+            let cell = match s {
+                Space::Floor => Cell::new('.', Rgb::GRAY),
+                Space::Vacant => Cell::new('L', Rgb::GREEN),
+                Space::Occupied => Cell::new('#', Rgb::RED),
+            };
+            frame.set(p.1 as usize, p.0 as usize, cell);
         }
+        frame
     }
 }
 
@@ -115,3 +143,21 @@ pub fn part2(input: &str) -> crate::Result<i32> {
     grid.stabilize_p2();
     Ok(grid.map.values().filter(|&v| *v == Space::Occupied).count() as i32)
 }
+
+pub fn viz(input: &str) -> crate::Result<Vec<Frame>> {
+    let mut frames = Vec::new();
+
+    let mut grid: Grid = input.parse()?;
+    frames.push(grid.frame());
+    while grid.step_p1() {
+        frames.push(grid.frame());
+    }
+
+    let mut grid: Grid = input.parse()?;
+    frames.push(grid.frame());
+    while grid.step_p2() {
+        frames.push(grid.frame());
+    }
+
+    Ok(frames)
+}
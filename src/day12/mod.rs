@@ -1,10 +1,21 @@
+use std::fmt;
 use std::str::FromStr;
 
+use crate::viz::{Cell, Frame, Rgb};
+
 #[derive(Debug)]
 enum Error {
     InvalidInput,
 }
 
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidInput => write!(f, "invalid input"),
+        }
+    }
+}
+
 #[derive(Debug)]
 enum Instruction {
     North(i32),
@@ -80,16 +91,20 @@ impl ShipP1 {
         }
     }
 
+    fn exec(&mut self, insn: &Instruction) {
+        match insn {
+            Instruction::North(n) => self.pos.1 -= n,
+            Instruction::South(n) => self.pos.1 += n,
+            Instruction::East(n) => self.pos.0 += n,
+            Instruction::West(n) => self.pos.0 -= n,
+            Instruction::Turn(n) => self.turn(*n),
+            Instruction::Forward(n) => self.mv(*n),
+        }
+    }
+
     fn instruct(&mut self, insns: &[Instruction]) {
         for i in insns {
-            match i {
-                Instruction::North(n) => self.pos.1 -= n,
-                Instruction::South(n) => self.pos.1 += n,
-                Instruction::East(n) => self.pos.0 += n,
-                Instruction::West(n) => self.pos.0 -= n,
-                Instruction::Turn(n) => self.turn(*n),
-                Instruction::Forward(n) => self.mv(*n),
-            }
+            self.exec(i);
         }
     }
 }
@@ -128,16 +143,20 @@ impl ShipP2 {
         self.pos.1 += self.wp.1 * n;
     }
 
+    fn exec(&mut self, insn: &Instruction) {
+        match insn {
+            Instruction::North(n) => self.wp.1 -= n,
+            Instruction::South(n) => self.wp.1 += n,
+            Instruction::East(n) => self.wp.0 += n,
+            Instruction::West(n) => self.wp.0 -= n,
+            Instruction::Turn(n) => self.turn(*n),
+            Instruction::Forward(n) => self.mv(*n),
+        }
+    }
+
     fn instruct(&mut self, insns: &[Instruction]) {
         for i in insns {
-            match i {
-                Instruction::North(n) => self.wp.1 -= n,
-                Instruction::South(n) => self.wp.1 += n,
-                Instruction::East(n) => self.wp.0 += n,
-                Instruction::West(n) => self.wp.0 -= n,
-                Instruction::Turn(n) => self.turn(*n),
-                Instruction::Forward(n) => self.mv(*n),
-            }
+            self.exec(i);
         }
     }
 }
@@ -148,3 +167,78 @@ pub fn part2(input: &str) -> crate::Result<i32> {
     ship.instruct(&insns);
     Ok(ship.pos.0.abs() + ship.pos.1.abs())
 }
+
+fn draw_line(frame: &mut Frame, a: (i32, i32), b: (i32, i32), cell: Cell) {
+    let n = std::cmp::max((b.0 - a.0).abs(), (b.1 - a.1).abs()).max(1);
+    for i in 0..=n {
+        let x = a.0 + (b.0 - a.0) * i / n;
+        let y = a.1 + (b.1 - a.1) * i / n;
+        frame.set(x as usize, y as usize, cell);
+    }
+}
+
+/// Draws the first `n` legs of `path`, scaled down so the whole path fits
+/// into a `max`x`max` frame.
+fn path_frame(path: &[(i32, i32)], n: usize, max: i32) -> Frame {
+    let min_x = path.iter().map(|p| p.0).min().unwrap_or(0);
+    let max_x = path.iter().map(|p| p.0).max().unwrap_or(0);
+    let min_y = path.iter().map(|p| p.1).min().unwrap_or(0);
+    let max_y = path.iter().map(|p| p.1).max().unwrap_or(0);
+    let extent = std::cmp::max(max_x - min_x, max_y - min_y) + 1;
+    let scale = (extent + max - 1) / max;
+
+    let to_frame = |p: (i32, i32)| ((p.0 - min_x) / scale, (p.1 - min_y) / scale);
+    let mut frame = Frame::new(
+        ((max_x - min_x) / scale + 1) as usize,
+        ((max_y - min_y) / scale + 1) as usize,
+    );
+    for leg in path[..=n].windows(2) {
+        draw_line(
+            &mut frame,
+            to_frame(leg[0]),
+            to_frame(leg[1]),
+            Cell::new('#', Rgb::BLUE),
+        );
+    }
+
+    let (x, y) = to_frame(path[0]);
+    frame.set(x as usize, y as usize, Cell::new('S', Rgb::GREEN));
+    let (x, y) = to_frame(path[n]);
+    frame.set(x as usize, y as usize, Cell::new('@', Rgb::RED));
+    frame
+}
+
+fn path_frames(path: &[(i32, i32)]) -> Vec<Frame> {
+    const FRAMES: usize = 20;
+    const SIZE: i32 = 160;
+
+    let step = std::cmp::max(path.len() / FRAMES, 1);
+    let mut frames: Vec<Frame> = (0..path.len())
+        .step_by(step)
+        .map(|n| path_frame(path, n, SIZE))
+        .collect();
+    frames.push(path_frame(path, path.len() - 1, SIZE));
+    frames
+}
+
+pub fn viz(input: &str) -> crate::Result<Vec<Frame>> {
+    let insns: Vec<Instruction> = input.lines().map(str::parse).collect::<Result<_, _>>()?;
+
+    let mut ship = ShipP1::new();
+    let mut path = vec![ship.pos];
+    for i in &insns {
+        ship.exec(i);
+        path.push(ship.pos);
+    }
+    let mut frames = path_frames(&path);
+
+    let mut ship = ShipP2::new();
+    let mut path = vec![ship.pos];
+    for i in &insns {
+        ship.exec(i);
+        path.push(ship.pos);
+    }
+    frames.extend(path_frames(&path));
+
+    Ok(frames)
+}
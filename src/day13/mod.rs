@@ -1,8 +1,18 @@
+use std::fmt;
+
 #[derive(Debug)]
 enum Error {
     InvalidInput,
 }
 
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidInput => write!(f, "invalid input"),
+        }
+    }
+}
+
 #[allow(clippy::many_single_char_names)]
 fn euclid_ext(a: i64, b: i64) -> (i64, i64, i64) {
     if b == 0 {
@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::fmt;
 use std::str::FromStr;
 
 #[derive(Debug)]
@@ -6,6 +7,14 @@ enum Error {
     InvalidInput,
 }
 
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidInput => write!(f, "invalid input"),
+        }
+    }
+}
+
 #[derive(Debug)]
 enum Instruction {
     Mask((i64, i64)),
@@ -1,8 +1,18 @@
+use std::fmt;
+
 #[derive(Debug)]
 enum Error {
     InvalidInput,
 }
 
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidInput => write!(f, "invalid input"),
+        }
+    }
+}
+
 fn find_nth(mut start: Vec<i32>, tgt: i32) -> crate::Result<i32> {
     let mut mem = Vec::new();
     let mut last = start
@@ -35,7 +45,7 @@ pub fn part1(input: &str) -> crate::Result<i32> {
         .rev()
         .collect::<Result<_, _>>()?;
 
-    Ok(find_nth(start, 2020)?)
+    find_nth(start, 2020)
 }
 
 pub fn part2(input: &str) -> crate::Result<i32> {
@@ -45,5 +55,5 @@ pub fn part2(input: &str) -> crate::Result<i32> {
         .rev()
         .collect::<Result<_, _>>()?;
 
-    Ok(find_nth(start, 30000000)?)
+    find_nth(start, 30000000)
 }
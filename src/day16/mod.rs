@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::fmt;
 use std::ops::RangeInclusive;
 use std::str::FromStr;
 
@@ -7,6 +8,14 @@ enum Error {
     InvalidInput,
 }
 
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidInput => write!(f, "invalid input"),
+        }
+    }
+}
+
 #[derive(Debug)]
 struct Range(RangeInclusive<i32>);
 
@@ -60,7 +69,7 @@ impl FromStr for Ticket {
 }
 
 // glorious!
-fn parse(s: &str) -> crate::Result<(RuleSet, Ticket, Vec<Ticket>)> {
+fn parse(s: &str) -> crate::Result<(RuleSet<'_>, Ticket, Vec<Ticket>)> {
     let mut ps = s.split("\n\n");
 
     let rules = ps
@@ -3,6 +3,8 @@ use std::hash::Hash;
 use std::iter;
 use std::str::FromStr;
 
+use crate::viz::{Cell as Pixel, Frame, Rgb};
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 enum Cell {
     Active,
@@ -91,6 +93,30 @@ impl<T: Point + Eq + Hash + Copy> Grid<T> {
     }
 }
 
+impl Grid<Point3d> {
+    /// Renders every z slice side by side, lowest z first.
+    fn frame(&self, bounds: (Point3d, Point3d)) -> Frame {
+        let (lo, hi) = bounds;
+        let (h, w) = ((hi.0 - lo.0 + 1) as usize, (hi.1 - lo.1 + 1) as usize);
+        let slices = (hi.2 - lo.2 + 1) as usize;
+
+        let mut frame = Frame::new(slices * (w + 1) - 1, h);
+        for z in 0..slices {
+            for r in 0..h {
+                for c in 0..w {
+                    let p = Point3d(lo.0 + r as i32, lo.1 + c as i32, lo.2 + z as i32);
+                    let pixel = match self.map.get(&p) {
+                        Some(Cell::Active) => Pixel::new('#', Rgb::YELLOW),
+                        _ => Pixel::new('.', Rgb::GRAY),
+                    };
+                    frame.set(z * (w + 1) + c, r, pixel);
+                }
+            }
+        }
+        frame
+    }
+}
+
 impl<T: Point + Eq + Hash> FromStr for Grid<T> {
     type Err = Box<dyn std::error::Error>;
 
@@ -124,6 +150,32 @@ pub fn part2(input: &str) -> crate::Result<i32> {
     Ok(grid.map.len() as i32)
 }
 
+pub fn viz(input: &str) -> crate::Result<Vec<Frame>> {
+    let mut grid: Grid<Point3d> = input.parse()?;
+    let mut grids = vec![grid.map.clone()];
+    for _ in 0..6 {
+        grid.step();
+        grids.push(grid.map.clone());
+    }
+
+    let points = || grids.iter().flat_map(|m| m.keys());
+    let lo = Point3d(
+        points().map(|p| p.0).min().unwrap_or(0),
+        points().map(|p| p.1).min().unwrap_or(0),
+        points().map(|p| p.2).min().unwrap_or(0),
+    );
+    let hi = Point3d(
+        points().map(|p| p.0).max().unwrap_or(0),
+        points().map(|p| p.1).max().unwrap_or(0),
+        points().map(|p| p.2).max().unwrap_or(0),
+    );
+
+    Ok(grids
+        .into_iter()
+        .map(|map| Grid { map }.frame((lo, hi)))
+        .collect())
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
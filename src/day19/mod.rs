@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::fmt;
 use std::str::FromStr;
 
 #[derive(Debug)]
@@ -6,6 +7,14 @@ enum Error {
     InvalidInput,
 }
 
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidInput => write!(f, "invalid input"),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 enum Rule {
     Refs(Vec<Vec<i32>>),
@@ -83,7 +92,7 @@ fn matches<'a>(
                 for r in opt.iter().rev() {
                     c.push(
                         rules
-                            .get(&r)
+                            .get(r)
                             .ok_or_else(|| crate::Error::boxed(Error::InvalidInput))?,
                     );
                 }
@@ -3,11 +3,21 @@ use std::fmt;
 use std::iter;
 use std::str::FromStr;
 
+use crate::viz::{Cell, Frame, Rgb};
+
 #[derive(Debug)]
 enum Error {
     InvalidInput,
 }
 
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidInput => write!(f, "invalid input"),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 struct Pair(i32, i32);
 
@@ -247,7 +257,7 @@ impl Image {
 
         let key = ps
             .iter()
-            .fold(0i32, |acc, p| match self.pixels.get(&p).unwrap().0 {
+            .fold(0i32, |acc, p| match self.pixels.get(p).unwrap().0 {
                 '#' => (acc << 1) + 1,
                 _ => acc << 1,
             });
@@ -307,7 +317,7 @@ impl FromStr for Tile {
             .next()
             .ok_or_else(|| crate::Error::boxed(Error::InvalidInput))?
             .chars()
-            .filter(|c| c.is_digit(10))
+            .filter(|c| c.is_ascii_digit())
             .collect::<String>()
             .parse()?;
 
@@ -387,7 +397,9 @@ pub fn part1(input: &str) -> crate::Result<i64> {
     Ok(p)
 }
 
-pub fn part2(input: &str) -> crate::Result<i64> {
+/// Assembles the cropped tiles into one image and marks every pixel that is
+/// part of a sea monster.
+fn find_monsters(input: &str) -> crate::Result<Image> {
     let mut tiles = solve(input)?;
 
     for tile in tiles.values_mut() {
@@ -441,6 +453,11 @@ pub fn part2(input: &str) -> crate::Result<i64> {
         }
     }
 
+    Ok(img)
+}
+
+pub fn part2(input: &str) -> crate::Result<i64> {
+    let img = find_monsters(input)?;
     let safe_cnt = img
         .pixels
         .values()
@@ -450,6 +467,22 @@ pub fn part2(input: &str) -> crate::Result<i64> {
     Ok(safe_cnt as i64)
 }
 
+pub fn viz(input: &str) -> crate::Result<Vec<Frame>> {
+    let img = find_monsters(input)?;
+
+    let mut frame = Frame::new(img.sz.0 as usize, img.sz.1 as usize);
+    for (p, (c, monster)) in &img.pixels {
+        let cell = match (c, monster) {
+            ('#', true) => Cell::new('O', Rgb::RED),
+            ('#', false) => Cell::new('#', Rgb::BLUE),
+            _ => Cell::new('.', Rgb::GRAY),
+        };
+        frame.set(p.0 as usize, p.1 as usize, cell);
+    }
+
+    Ok(vec![frame])
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,13 +1,22 @@
 use std::collections::{HashMap, HashSet};
+use std::fmt;
 
 #[derive(Debug)]
 enum Error {
     InvalidInput,
 }
 
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidInput => write!(f, "invalid input"),
+        }
+    }
+}
+
 type ListEntry<'a> = (Vec<&'a str>, Vec<&'a str>);
 
-fn parse(s: &str) -> crate::Result<(HashSet<&str>, Vec<ListEntry>)> {
+fn parse(s: &str) -> crate::Result<(HashSet<&str>, Vec<ListEntry<'_>>)> {
     let mut all_ingredients = HashSet::new();
     let mut list = Vec::new();
     for l in s.lines() {
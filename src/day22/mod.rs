@@ -1,10 +1,19 @@
 use std::collections::{HashSet, VecDeque};
+use std::fmt;
 
 #[derive(Debug)]
 enum Error {
     InvalidInput,
 }
 
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidInput => write!(f, "invalid input"),
+        }
+    }
+}
+
 fn parse(s: &str) -> crate::Result<(VecDeque<i32>, VecDeque<i32>)> {
     let mut decks = Vec::new();
     for p in s.split("\n\n") {
@@ -1,6 +1,8 @@
 use std::collections::{HashMap, HashSet};
 use std::iter;
 
+use crate::viz::{Cell, Frame, Rgb};
+
 fn neighbors(p: (i32, i32)) -> impl Iterator<Item = (i32, i32)> {
     vec![
         (p.0 + 1, p.1),
@@ -81,9 +83,7 @@ fn parse(s: &str) -> Vec<Vec<Dir>> {
     insns
 }
 
-pub fn part1(input: &str) -> crate::Result<i32> {
-    let insns = parse(input);
-
+fn initial_tiles(insns: Vec<Vec<Dir>>) -> HashMap<(i32, i32), Color> {
     let mut colors: HashMap<(i32, i32), Color> = HashMap::new();
     for i in insns {
         let mut p = (0, 0);
@@ -95,47 +95,88 @@ pub fn part1(input: &str) -> crate::Result<i32> {
             colors.insert(p, Color::Black);
         }
     }
-
-    Ok(colors.len() as i32)
+    colors
 }
 
-pub fn part2(input: &str) -> crate::Result<i32> {
-    let insns = parse(input);
+fn flip_day(colors: &HashMap<(i32, i32), Color>) -> HashMap<(i32, i32), Color> {
+    let mut next = HashMap::new();
 
-    let mut colors: HashMap<(i32, i32), Color> = HashMap::new();
-    for i in insns {
-        let mut p = (0, 0);
-        for d in i {
-            p = d.apply(p);
-        }
+    let to_check: HashSet<(i32, i32)> = colors
+        .keys()
+        .flat_map(|p| iter::once(*p).chain(neighbors(*p)))
+        .collect();
 
-        if colors.remove(&p).is_none() {
-            colors.insert(p, Color::Black);
+    for p in to_check {
+        let color = *colors.get(&p).unwrap_or(&Color::White);
+        let black_cnt = neighbors(p).filter(|p| colors.contains_key(p)).count();
+
+        match (color, black_cnt) {
+            (Color::White, 2) | (Color::Black, 1..=2) => {
+                next.insert(p, Color::Black);
+            }
+            _ => (),
         }
     }
 
-    for _ in 0..100 {
-        let mut next = HashMap::new();
+    next
+}
 
-        let to_check: HashSet<(i32, i32)> = colors
-            .keys()
-            .flat_map(|p| iter::once(*p).chain(neighbors(*p)))
-            .collect();
+pub fn part1(input: &str) -> crate::Result<i32> {
+    let colors = initial_tiles(parse(input));
+    Ok(colors.len() as i32)
+}
 
-        for p in to_check {
-            let color = *colors.get(&p).unwrap_or(&Color::White);
-            let black_cnt = neighbors(p).filter(|p| colors.contains_key(&p)).count();
+pub fn part2(input: &str) -> crate::Result<i32> {
+    let mut colors = initial_tiles(parse(input));
+    for _ in 0..100 {
+        colors = flip_day(&colors);
+    }
+    Ok(colors.len() as i32)
+}
 
-            match (color, black_cnt) {
-                (Color::White, 2) | (Color::Black, 1..=2) => {
-                    next.insert(p, Color::Black);
-                }
-                _ => (),
+/// Lays out the hex grid on doubled columns, with north at the top, so that
+/// each tile is two cells wide and neighboring rows are offset by one cell.
+fn frame(colors: &HashMap<(i32, i32), Color>, lo: (i32, i32), hi: (i32, i32)) -> Frame {
+    let mut frame = Frame::new((hi.0 - lo.0 + 2) as usize, (hi.1 - lo.1 + 1) as usize);
+    for y in lo.1..=hi.1 {
+        for x in lo.0..=hi.0 {
+            // Only every other doubled column is a tile center in each row
+            if (x + y).rem_euclid(2) != 0 {
+                continue;
             }
+
+            let p = ((x + y) / 2, -y);
+            let cell = match colors.get(&p) {
+                Some(Color::Black) => Cell::new('#', Rgb::WHITE),
+                _ => Cell::new('.', Rgb::GRAY),
+            };
+            frame.set((x - lo.0) as usize, (y - lo.1) as usize, cell);
         }
+    }
+    frame
+}
 
-        colors = next;
+pub fn viz(input: &str) -> crate::Result<Vec<Frame>> {
+    let mut days = vec![initial_tiles(parse(input))];
+    for _ in 0..100 {
+        let next = flip_day(days.last().unwrap());
+        days.push(next);
     }
 
-    Ok(colors.len() as i32)
+    // Tile (q, r) sits at doubled column 2q + r and row -r
+    let coords = || {
+        days.iter()
+            .flat_map(|d| d.keys())
+            .map(|p| (2 * p.0 + p.1, -p.1))
+    };
+    let lo = (
+        coords().map(|p| p.0).min().unwrap_or(0) - 1,
+        coords().map(|p| p.1).min().unwrap_or(0),
+    );
+    let hi = (
+        coords().map(|p| p.0).max().unwrap_or(0) + 1,
+        coords().map(|p| p.1).max().unwrap_or(0),
+    );
+
+    Ok(days.iter().step_by(10).map(|d| frame(d, lo, hi)).collect())
 }
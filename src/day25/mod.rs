@@ -1,8 +1,18 @@
+use std::fmt;
+
 #[derive(Debug)]
 enum Error {
     InvalidInput,
 }
 
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidInput => write!(f, "invalid input"),
+        }
+    }
+}
+
 fn transform(subject: i64, loop_sz: i64) -> i64 {
     let mut res = 1;
     for _ in 0..loop_sz {
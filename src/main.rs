@@ -23,12 +23,20 @@ mod day22;
 mod day23;
 mod day24;
 mod day25;
+mod viz;
 
-use std::{env, error, fmt, fs, result, time};
+use std::collections::HashMap;
+use std::{env, error, fmt, fs, io, path, process, result, str, time};
 
 #[derive(Debug)]
 struct UsageError;
 
+impl fmt::Display for UsageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid arguments")
+    }
+}
+
 type Result<T> = result::Result<T, Box<dyn error::Error>>;
 
 #[derive(Debug)]
@@ -42,18 +50,76 @@ impl<T> Error<T> {
     }
 }
 
-impl<T: fmt::Debug> fmt::Display for Error<T> {
+impl<T: fmt::Display> fmt::Display for Error<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{:?}", self.err)
+        write!(f, "{}", self.err)
     }
 }
 
-impl<T: fmt::Debug> error::Error for Error<T> {
+impl<T: fmt::Debug + fmt::Display> error::Error for Error<T> {
     fn cause(&self) -> Option<&dyn error::Error> {
         Some(self)
     }
 }
 
+/// Day specific options that never take a value, so the argument after them
+/// is left for the input path or the next option.
+const SWITCHES: &[&str] = &[
+    "all",
+    "count",
+    "lenient",
+    "typed",
+    "map",
+    "histogram",
+    "dot",
+    "paths",
+    "deepest",
+    "breakdown",
+    "ignore-loops",
+    "debug",
+    "ext",
+    "repair",
+    "format",
+    "disassemble",
+    "listing",
+    "stdin",
+];
+
+/// Day specific `--name value` or `--name=value` command line options. An
+/// option given without a value takes its default.
+#[derive(Debug, Default)]
+struct Opts {
+    map: HashMap<String, String>,
+}
+
+impl Opts {
+    fn has(&self, name: &str) -> bool {
+        self.map.contains_key(name)
+    }
+
+    fn get<T>(&self, name: &str, default: T) -> Result<T>
+    where
+        T: str::FromStr,
+        T::Err: Into<Box<dyn error::Error>>,
+    {
+        match self.map.get(name) {
+            Some(v) if !v.is_empty() => v.parse().map_err(Into::into),
+            _ => Ok(default),
+        }
+    }
+
+    fn get_opt<T>(&self, name: &str) -> Result<Option<T>>
+    where
+        T: str::FromStr,
+        T::Err: Into<Box<dyn error::Error>>,
+    {
+        match self.map.get(name) {
+            Some(v) if !v.is_empty() => v.parse().map(Some).map_err(Into::into),
+            _ => Ok(None),
+        }
+    }
+}
+
 fn print_time(d: time::Duration) {
     println!(
         "> {}.{:03} {:03} {:03} seconds",
@@ -73,13 +139,44 @@ fn time<F: Fn(A) -> B, A, B>(f: F, a: A) -> B {
 }
 
 fn usage() -> Result<()> {
-    eprintln!("usage: aoc2020 <day> [<input>]");
+    eprintln!("usage: aoc2020 <day> [<input>] [--<option> [<value>]...]");
+    eprintln!("       aoc2020 viz <day> [text|ppm|pgm|svg] [<input>]");
     Err(Error::boxed(UsageError {}))
 }
 
-fn main() -> Result<()> {
-    let (day, input) = {
-        let mut args = env::args().skip(1);
+fn viz(day: u32, format: viz::Format, input: &str) -> Result<()> {
+    let frames = match day {
+        11 => day11::viz(input)?,
+        12 => day12::viz(input)?,
+        17 => day17::viz(input)?,
+        20 => day20::viz(input)?,
+        24 => day24::viz(input)?,
+        _ => {
+            eprintln!("No visualization for day {}", day);
+            return usage();
+        }
+    };
+
+    let dir = path::PathBuf::from(format!("viz/day{:02}", day));
+    viz::emit(&frames, format, &dir)?;
+    Ok(())
+}
+
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("Error: {}", err);
+        process::exit(1);
+    }
+}
+
+fn run() -> Result<()> {
+    let mut args = env::args().skip(1).peekable();
+    let is_viz = args.peek().map(|a| a == "viz").unwrap_or(false);
+    if is_viz {
+        args.next();
+    }
+
+    let (day, format, input, opts) = {
         let d = if let Some(d) = args.next() {
             if let Ok(d) = d.parse() {
                 d
@@ -92,57 +189,362 @@ fn main() -> Result<()> {
             return usage();
         };
 
-        let i = args.next().unwrap_or_else(|| format!("input/day{:02}", d));
-        let i = if let Ok(i) = fs::read_to_string(&i) {
+        let f = match args.peek().map(|f| f.parse::<viz::Format>()) {
+            Some(Ok(f)) if is_viz => {
+                args.next();
+                f
+            }
+            _ => viz::Format::Text,
+        };
+
+        let mut opts = Opts::default();
+        let mut i = None;
+        while let Some(arg) = args.next() {
+            if let Some(name) = arg.strip_prefix("--") {
+                let (name, value) = match name.find('=') {
+                    Some(idx) => (name[..idx].to_string(), name[idx + 1..].to_string()),
+                    None if SWITCHES.contains(&name) => (name.to_string(), String::new()),
+                    None => match args.peek() {
+                        Some(v) if !v.starts_with("--") => (name.to_string(), args.next().unwrap()),
+                        _ => (name.to_string(), String::new()),
+                    },
+                };
+                if SWITCHES.contains(&name.as_str()) && !value.is_empty() {
+                    eprintln!("Option '--{}' takes no value", name);
+                    return usage();
+                }
+                opts.map.insert(name, value);
+            } else if i.is_none() {
+                i = Some(arg);
+            } else {
+                eprintln!("Unexpected argument: '{}'", arg);
+                return usage();
+            }
+        }
+
+        // Input read from stdin replaces the input file, which only day 9
+        // supports
+        let i = i.unwrap_or_else(|| format!("input/day{:02}", d));
+        let i = if opts.has("stdin") {
+            if is_viz || d != 9 {
+                eprintln!("Option '--stdin' is only supported for day 9");
+                return usage();
+            }
+            String::new()
+        } else if let Ok(i) = fs::read_to_string(&i) {
             i
         } else {
             eprintln!("No such file: '{}'", &i);
             return usage();
         };
 
-        (d, i)
+        (d, f, i, opts)
     };
 
+    if is_viz {
+        return viz(day, format, input.trim());
+    }
+
     match day {
         1 => {
-            println!("Part 1: {}", time(day01::part1, input.trim())?);
-            println!("Part 2: {}", time(day01::part2, input.trim())?);
+            let target = opts.get("target", 2020)?;
+            let k = opts.get("k", 2)?;
+            if opts.has("all") || opts.has("count") {
+                if opts.has("all") {
+                    for vs in day01::list_k_sums(input.trim(), target, k)? {
+                        println!("{:?}", vs);
+                    }
+                }
+                if opts.has("count") {
+                    println!(
+                        "Number of {}-sums to {}: {}",
+                        k,
+                        target,
+                        time(|i| day01::num_k_sums(i, target, k), input.trim())?
+                    );
+                }
+            } else if opts.has("target") || opts.has("k") {
+                println!(
+                    "{}-sum to {}: {}",
+                    k,
+                    target,
+                    time(|i| day01::product_of_k_sum(i, target, k), input.trim())?
+                );
+            } else {
+                println!("Part 1: {}", time(day01::part1, input.trim())?);
+                println!("Part 2: {}", time(day01::part2, input.trim())?);
+            }
         }
         2 => {
             println!("Part 1: {}", time(day02::part1, input.trim())?);
             println!("Part 2: {}", time(day02::part2, input.trim())?);
+            let positions = opts.get("positions", day02::Positions::Char)?;
+            if opts.has("positions") {
+                println!(
+                    "Part 2 ({:?} positions): {}",
+                    positions,
+                    time(|i| day02::part2_with(i, positions), input.trim())?
+                );
+            }
+            if opts.has("policies") {
+                let policies: String = opts.get("policies", String::new())?;
+                print!("{}", day02::report(input.trim(), &policies, positions)?);
+            }
         }
         3 => {
             println!("Part 1: {}", time(day03::part1, input.trim())?);
             println!("Part 2: {}", time(day03::part2, input.trim())?);
+            if opts.has("max-slope") {
+                let bound = opts.get("max-slope", 7)?;
+                let [fewest, most] = day03::search_slopes(input.trim(), bound)?;
+                for (what, ((right, down), cnt)) in [("Fewest", fewest), ("Most", most)].iter() {
+                    println!("{} trees: {} (right {}, down {})", what, cnt, right, down);
+                }
+            }
+            if opts.has("render") {
+                let slope: String = opts.get("render", String::new())?;
+                let slope = day03::parse_slope(&slope)?;
+                print!("{}", day03::render_path(input.trim(), slope)?);
+            }
         }
         4 => {
-            println!("Part 1: {}", time(day04::part1, input.trim())?);
-            println!("Part 2: {}", time(day04::part2, input.trim())?);
+            // Every lenient count skips the same records, so their warnings
+            // are only shown once
+            let lenient = opts.has("lenient");
+            if lenient {
+                let (cnt, warnings) = time(
+                    |i| day04::count_complete(i, day04::DEFAULT_SCHEMA, true),
+                    input.trim(),
+                )?;
+                for w in warnings {
+                    eprintln!("warning: {}", w);
+                }
+                println!("Part 1: {}", cnt);
+                let (cnt, _) = time(
+                    |i| day04::count_valid(i, day04::DEFAULT_SCHEMA, true),
+                    input.trim(),
+                )?;
+                println!("Part 2: {}", cnt);
+            } else {
+                println!("Part 1: {}", time(day04::part1, input.trim())?);
+                println!("Part 2: {}", time(day04::part2, input.trim())?);
+            }
+            let schema = if opts.has("schema") {
+                let path: String = opts.get("schema", String::new())?;
+                let schema = fs::read_to_string(&path)?;
+                let (cnt, _) = time(|i| day04::count_valid(i, &schema, lenient), input.trim())?;
+                println!("Valid with {}: {}", path, cnt);
+                Some(schema)
+            } else {
+                None
+            };
+            if opts.has("report") {
+                let format = opts.get("report", day04::ReportFormat::Text)?;
+                let schema = schema.as_deref().unwrap_or(day04::DEFAULT_SCHEMA);
+                let (report, _) = day04::report(input.trim(), schema, format, lenient)?;
+                print!("{}", report);
+            }
+            if opts.has("typed") {
+                let (ps, warnings) = day04::passports(input.trim(), lenient)?;
+                for w in warnings {
+                    eprintln!("warning: {}", w);
+                }
+                for p in ps {
+                    println!("{}", p);
+                }
+            }
         }
         5 => {
-            println!("Part 1: {}", time(day05::part1, input.trim())?);
-            println!("Part 2: {}", time(day05::part2, input.trim())?);
+            let layout = opts.get("layout", day05::Layout::default())?;
+            if opts.has("layout") {
+                println!(
+                    "Part 1 ({} layout): {}",
+                    layout,
+                    time(|i| day05::highest_id(i, layout), input.trim())?
+                );
+                println!(
+                    "Part 2 ({} layout): {}",
+                    layout,
+                    time(|i| day05::own_seat(i, layout), input.trim())?
+                );
+            } else {
+                println!("Part 1: {}", time(day05::part1, input.trim())?);
+                println!("Part 2: {}", time(day05::part2, input.trim())?);
+            }
+            if opts.has("encode") {
+                let seat: String = opts.get("encode", String::new())?;
+                println!("Pass for {}: {}", seat, layout.encode_seat(&seat)?);
+            }
+            if opts.has("decode") {
+                let pass: String = opts.get("decode", String::new())?;
+                let (row, col) = layout.decode(&pass)?;
+                println!(
+                    "{}: row {}, column {}, seat ID {}",
+                    pass,
+                    row,
+                    col,
+                    layout.id(row, col)
+                );
+            }
+            if opts.has("map") {
+                print!("{}", day05::seat_map(input.trim(), layout)?);
+            }
         }
         6 => {
             println!("Part 1: {}", time(day06::part1, input.trim())?);
             println!("Part 2: {}", time(day06::part2, input.trim())?);
+            if opts.has("quorum") {
+                let quorum = opts.get("quorum", day06::Quorum::Any)?;
+                println!(
+                    "Questions meeting {:?}: {}",
+                    quorum,
+                    time(|i| day06::count_quorum(i, quorum), input.trim())?
+                );
+            }
+            if opts.has("histogram") {
+                print!("{}", day06::histogram(input.trim())?);
+            }
         }
         7 => {
             println!("Part 1: {}", time(day07::part1, input.trim())?);
             println!("Part 2: {}", time(day07::part2, input.trim())?);
+            if opts.has("color") {
+                let clr = opts.get("color", "shiny gold".to_string())?;
+                println!(
+                    "Bags that can hold {}: {}",
+                    clr,
+                    time(|i| day07::containers(i, &clr), input.trim())?
+                );
+                println!(
+                    "Bags inside {}: {}",
+                    clr,
+                    time(|i| day07::contained(i, &clr), input.trim())?
+                );
+            }
+            let from: Option<String> = opts.get_opt("from")?;
+            let to: Option<String> = opts.get_opt("to")?;
+            if opts.has("dot") {
+                print!(
+                    "{}",
+                    day07::dot(input.trim(), from.as_deref(), to.as_deref())?
+                );
+            }
+            if opts.has("paths") {
+                let outer = from.as_deref().unwrap_or("shiny gold");
+                let inner = to.as_deref().unwrap_or("shiny gold");
+                print!("{}", day07::paths(input.trim(), outer, inner)?);
+            }
+            if opts.has("deepest") {
+                println!("{}", day07::deepest(input.trim(), from.as_deref())?);
+            }
+            if opts.has("breakdown") {
+                let clr = opts.get("color", "shiny gold".to_string())?;
+                print!("{}", day07::breakdown(input.trim(), &clr)?);
+            }
         }
         8 => {
-            println!("Part 1: {}", time(day08::part1, input.trim())?);
-            println!("Part 2: {}", time(day08::part2, input.trim())?);
+            // The tools work on any program, not just those that loop like
+            // the puzzle input
+            let tools = [
+                "max-steps",
+                "ignore-loops",
+                "trace",
+                "break",
+                "watch",
+                "debug",
+                "ext",
+                "repair",
+                "format",
+                "disassemble",
+                "listing",
+            ];
+            if !tools.iter().any(|t| opts.has(t)) {
+                println!("Part 1: {}", time(day08::part1, input.trim())?);
+                println!("Part 2: {}", time(day08::part2, input.trim())?);
+            }
+            let termination = day08::Termination {
+                detect_loops: !opts.has("ignore-loops"),
+                max_steps: opts.get_opt("max-steps")?,
+            };
+            if opts.has("max-steps") || opts.has("ignore-loops") {
+                println!(
+                    "Run: {:?}",
+                    time(|i| day08::run_with(i, termination), input.trim())?
+                );
+            }
+            if opts.has("trace") {
+                let path: String = opts.get("trace", String::new())?;
+                let mut out = io::BufWriter::new(fs::File::create(&path)?);
+                let res = day08::run_traced(input.trim(), termination, &mut out)?;
+                println!("Traced {:?} to {}", res, path);
+            }
+            let breakpoints: String = opts.get("break", String::new())?;
+            let watches: String = opts.get("watch", String::new())?;
+            if opts.has("debug") {
+                let stdout = io::stdout();
+                day08::debug_session(
+                    input.trim(),
+                    &breakpoints,
+                    &watches,
+                    io::stdin().lock(),
+                    stdout.lock(),
+                )?;
+            } else if opts.has("break") || opts.has("watch") {
+                print!("{}", day08::debug(input.trim(), &breakpoints, &watches)?);
+            }
+            if opts.has("ext") {
+                println!(
+                    "Extended run: {}",
+                    day08::run_extended(input.trim(), termination)?
+                );
+            }
+            if opts.has("repair") {
+                println!("{}", time(day08::repair, input.trim())?);
+            }
+            if opts.has("format") {
+                print!("{}", day08::format_source(input.trim())?);
+            }
+            if opts.has("disassemble") {
+                print!("{}", day08::disassembly(input.trim())?);
+            }
+            if opts.has("listing") {
+                print!("{}", day08::listing(input.trim())?);
+            }
         }
         9 => {
-            println!("Part 1: {}", time(day09::part1, input.trim())?);
-            println!("Part 2: {}", time(day09::part2, input.trim())?);
+            if opts.has("stdin") {
+                let len = opts.get("preamble", 25)?;
+                let stdout = io::stdout();
+                let invalid = day09::validate_stream(io::stdin().lock(), len, stdout.lock())?;
+                println!("Invalid numbers: {}", invalid);
+            } else if opts.has("preamble") {
+                let len = opts.get("preamble", 25)?;
+                println!(
+                    "Part 1 (preamble {}): {}",
+                    len,
+                    time(|i| day09::invalid_number(i, len), input.trim())?
+                );
+                println!(
+                    "Part 2 (preamble {}): {}",
+                    len,
+                    time(|i| day09::weakness(i, len), input.trim())?
+                );
+            } else {
+                println!("Part 1: {}", time(day09::part1, input.trim())?);
+                println!("Part 2: {}", time(day09::part2, input.trim())?);
+            }
         }
         10 => {
             println!("Part 1: {}", time(day10::part1, input.trim())?);
             println!("Part 2: {}", time(day10::part2, input.trim())?);
+            if opts.has("max-step") {
+                let step = opts.get("max-step", 3)?;
+                println!(
+                    "Part 2 (max step {}): {}",
+                    step,
+                    time(|i| day10::arrangements(i, step), input.trim())?
+                );
+            }
         }
         11 => {
             println!("Part 1: {}", time(day11::part1, input.trim())?);
@@ -507,6 +909,18 @@ mod tests {
         let inp = include_str!("../input/day25");
         assert_eq!(crate::day25::part1(inp.trim()).unwrap(), 4126980);
     }
+
+    #[test]
+    fn opts() {
+        let mut opts = crate::Opts::default();
+        opts.map.insert("max-step".to_string(), String::new());
+        opts.map.insert("k".to_string(), "4".to_string());
+        assert!(opts.has("max-step"));
+        assert_eq!(opts.get("max-step", 3).unwrap(), 3);
+        assert_eq!(opts.get_opt::<u32>("max-step").unwrap(), None);
+        assert_eq!(opts.get("k", 2).unwrap(), 4);
+        assert_eq!(opts.get("target", 2020).unwrap(), 2020);
+    }
 }
 
 // vim macro to prepare new day..
@@ -0,0 +1,237 @@
+use std::fmt;
+use std::fmt::Write as _;
+use std::path::Path;
+use std::str::FromStr;
+use std::{fs, io};
+
+#[derive(Debug)]
+enum Error {
+    InvalidFormat,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidFormat => write!(f, "invalid format, expected text, ppm, pgm or svg"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl Rgb {
+    pub const BLACK: Rgb = Rgb(0, 0, 0);
+    pub const WHITE: Rgb = Rgb(255, 255, 255);
+    pub const GRAY: Rgb = Rgb(96, 96, 96);
+    pub const RED: Rgb = Rgb(220, 50, 47);
+    pub const GREEN: Rgb = Rgb(133, 153, 0);
+    pub const BLUE: Rgb = Rgb(38, 139, 210);
+    pub const YELLOW: Rgb = Rgb(181, 137, 0);
+
+    fn luma(self) -> u8 {
+        ((299 * self.0 as u32 + 587 * self.1 as u32 + 114 * self.2 as u32) / 1000) as u8
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cell {
+    pub c: char,
+    pub color: Rgb,
+}
+
+impl Cell {
+    pub fn new(c: char, color: Rgb) -> Self {
+        Cell { c, color }
+    }
+}
+
+const BLANK: Cell = Cell {
+    c: ' ',
+    color: Rgb::BLACK,
+};
+
+/// A rectangular grid of cells, each with a character for text output and a
+/// color for image output.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    w: usize,
+    h: usize,
+    cells: Vec<Cell>,
+}
+
+impl Frame {
+    pub fn new(w: usize, h: usize) -> Self {
+        Frame {
+            w,
+            h,
+            cells: vec![BLANK; w * h],
+        }
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, cell: Cell) {
+        if x < self.w && y < self.h {
+            self.cells[y * self.w + x] = cell;
+        }
+    }
+
+    fn rows(&self) -> impl Iterator<Item = &[Cell]> {
+        self.cells.chunks(self.w.max(1))
+    }
+
+    /// Text with 24-bit ANSI foreground colors, one line per row.
+    pub fn ansi(&self) -> String {
+        let mut s = String::new();
+        for row in self.rows() {
+            let mut cur = None;
+            for cell in row {
+                if cur != Some(cell.color) {
+                    let Rgb(r, g, b) = cell.color;
+                    write!(s, "\x1b[38;2;{};{};{}m", r, g, b).unwrap();
+                    cur = Some(cell.color);
+                }
+                s.push(cell.c);
+            }
+            s.push_str("\x1b[0m\n");
+        }
+        s
+    }
+
+    /// Binary PPM (P6), each cell drawn as a `scale`x`scale` square.
+    pub fn ppm(&self, scale: usize) -> Vec<u8> {
+        let mut out = format!("P6\n{} {}\n255\n", self.w * scale, self.h * scale).into_bytes();
+        for row in self.rows() {
+            for _ in 0..scale {
+                for cell in row {
+                    for _ in 0..scale {
+                        out.extend_from_slice(&[cell.color.0, cell.color.1, cell.color.2]);
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    /// Binary PGM (P5), using the luma of each cell's color.
+    pub fn pgm(&self, scale: usize) -> Vec<u8> {
+        let mut out = format!("P5\n{} {}\n255\n", self.w * scale, self.h * scale).into_bytes();
+        for row in self.rows() {
+            for _ in 0..scale {
+                for cell in row {
+                    out.extend(std::iter::repeat_n(cell.color.luma(), scale));
+                }
+            }
+        }
+        out
+    }
+
+    /// SVG with one rect per non-blank cell on a black background.
+    pub fn svg(&self, scale: usize) -> String {
+        let (w, h) = (self.w * scale, self.h * scale);
+        let mut s = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\">\n\
+             <rect width=\"{}\" height=\"{}\" fill=\"black\"/>\n",
+            w, h, w, h
+        );
+        for (y, row) in self.rows().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                if *cell == BLANK {
+                    continue;
+                }
+                let Rgb(r, g, b) = cell.color;
+                writeln!(
+                    s,
+                    "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"rgb({},{},{})\"/>",
+                    x * scale,
+                    y * scale,
+                    scale,
+                    scale,
+                    r,
+                    g,
+                    b
+                )
+                .unwrap();
+            }
+        }
+        s.push_str("</svg>\n");
+        s
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Text,
+    Ppm,
+    Pgm,
+    Svg,
+}
+
+impl Format {
+    fn ext(self) -> &'static str {
+        match self {
+            Format::Text => "txt",
+            Format::Ppm => "ppm",
+            Format::Pgm => "pgm",
+            Format::Svg => "svg",
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = Box<dyn std::error::Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Format::Text),
+            "ppm" => Ok(Format::Ppm),
+            "pgm" => Ok(Format::Pgm),
+            "svg" => Ok(Format::Svg),
+            _ => Err(crate::Error::boxed(Error::InvalidFormat)),
+        }
+    }
+}
+
+const SCALE: usize = 4;
+
+/// Prints text frames to stdout, or writes image frames to `dir` as
+/// `frame0000.<ext>`, `frame0001.<ext>`, ...
+pub fn emit(frames: &[Frame], format: Format, dir: &Path) -> io::Result<()> {
+    if format == Format::Text {
+        for (i, f) in frames.iter().enumerate() {
+            println!("Frame {}:\n{}", i, f.ansi());
+        }
+        return Ok(());
+    }
+
+    fs::create_dir_all(dir)?;
+    for (i, f) in frames.iter().enumerate() {
+        let path = dir.join(format!("frame{:04}.{}", i, format.ext()));
+        match format {
+            Format::Ppm => fs::write(&path, f.ppm(SCALE))?,
+            Format::Pgm => fs::write(&path, f.pgm(SCALE))?,
+            Format::Svg => fs::write(&path, f.svg(SCALE))?,
+            Format::Text => unreachable!(),
+        }
+    }
+    println!("Wrote {} frames to {}", frames.len(), dir.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode() {
+        let mut f = Frame::new(2, 1);
+        f.set(1, 0, Cell::new('#', Rgb::WHITE));
+
+        assert_eq!(f.ppm(1), b"P6\n2 1\n255\n\0\0\0\xff\xff\xff");
+        assert_eq!(f.pgm(2), b"P5\n4 2\n255\n\0\0\xff\xff\0\0\xff\xff");
+        assert_eq!(f.svg(1).matches("<rect").count(), 2);
+        assert_eq!(
+            f.ansi(),
+            "\x1b[38;2;0;0;0m \x1b[38;2;255;255;255m#\x1b[0m\n"
+        );
+    }
+}